@@ -0,0 +1,299 @@
+use crate::error::CoreError;
+use crate::git::{discover_repository, head_commit, require_workdir};
+use crate::history::commit_info;
+use crate::types::CommitInfo;
+use git2::{Oid, Repository, RepositoryState};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+fn hooks_dir(repo: &Repository, workdir: &Path) -> PathBuf {
+    let configured = repo
+        .config()
+        .ok()
+        .and_then(|c| c.get_path("core.hooksPath").ok());
+    match configured {
+        Some(path) if path.is_absolute() => path,
+        Some(path) => workdir.join(path),
+        None => repo.commondir().join("hooks"),
+    }
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    path.metadata()
+        .is_ok_and(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file()
+}
+
+fn run_hook(repo: &Repository, name: &str, args: &[&Path]) -> Result<(), CoreError> {
+    let workdir = require_workdir(repo)?;
+    let hook = hooks_dir(repo, workdir).join(name);
+    if !is_executable(&hook) {
+        return Ok(());
+    }
+
+    let output = Command::new(&hook)
+        .args(args)
+        .current_dir(workdir)
+        .env("GIT_INDEX_FILE", repo.path().join("index"))
+        .env("GIT_EDITOR", ":")
+        .output()
+        .map_err(|e| CoreError::io(&hook, e))?;
+    if output.status.success() {
+        return Ok(());
+    }
+
+    let mut combined = String::from_utf8_lossy(&output.stdout).into_owned();
+    combined.push_str(&String::from_utf8_lossy(&output.stderr));
+    Err(CoreError::Hook {
+        hook: name.to_owned(),
+        exit_code: output.status.code(),
+        output: combined.trim_end().to_owned(),
+    })
+}
+
+/// Runs the `commit-msg` hook, which may rewrite the message file, and returns the final message.
+fn commit_msg_hook(repo: &Repository, message: &str) -> Result<String, CoreError> {
+    let message_path = repo.path().join("COMMIT_EDITMSG");
+    std::fs::write(&message_path, message).map_err(|e| CoreError::io(&message_path, e))?;
+    run_hook(repo, "commit-msg", &[&message_path])?;
+    std::fs::read_to_string(&message_path).map_err(|e| CoreError::io(&message_path, e))
+}
+
+/// Fails with `NothingToCommit` unless the index differs from HEAD, a merge is being
+/// concluded, or `amend` has a commit to replace.
+fn ensure_changes(repo: &Repository, amend: bool) -> Result<(), CoreError> {
+    let Some(head) = head_commit(repo) else {
+        return if amend {
+            Err(CoreError::NothingToCommit)
+        } else {
+            Ok(())
+        };
+    };
+    if amend || repo.state() == RepositoryState::Merge {
+        return Ok(());
+    }
+    let mut index = repo.index()?;
+    index.read(true)?;
+    if head.tree_id() == index.write_tree()? {
+        return Err(CoreError::NothingToCommit);
+    }
+    Ok(())
+}
+
+/// Commits the index as HEAD's child, or replaces HEAD when `amend` is set, running the
+/// `pre-commit` and `commit-msg` hooks first. Nothing runs when there is nothing to commit.
+pub fn create_commit(
+    repo_path: &Path,
+    message: &str,
+    amend: bool,
+) -> Result<CommitInfo, CoreError> {
+    let repo = discover_repository(repo_path)?;
+    create_commit_with_repo(&repo, message, amend)
+}
+
+pub(crate) fn create_commit_with_repo(
+    repo: &Repository,
+    message: &str,
+    amend: bool,
+) -> Result<CommitInfo, CoreError> {
+    ensure_changes(repo, amend)?;
+    run_hook(repo, "pre-commit", &[])?;
+    let message = commit_msg_hook(repo, message)?;
+    let message = git2::message_prettify(message, Some(b'#'))?;
+    if message.is_empty() {
+        return Err(CoreError::EmptyCommitMessage);
+    }
+
+    let mut index = repo.index()?;
+    index.read(true)?;
+    let tree = repo.find_tree(index.write_tree()?)?;
+    let signature = repo.signature()?;
+    let head = head_commit(repo);
+
+    let id = if amend {
+        let head = head.ok_or(CoreError::NothingToCommit)?;
+        head.amend(
+            Some("HEAD"),
+            None,
+            Some(&signature),
+            None,
+            Some(&message),
+            Some(&tree),
+        )?
+    } else {
+        let merging = repo.state() == RepositoryState::Merge;
+        if !merging && head.as_ref().is_some_and(|h| h.tree_id() == tree.id()) {
+            return Err(CoreError::NothingToCommit);
+        }
+
+        let mut parents: Vec<git2::Commit> = head.into_iter().collect();
+        if merging {
+            let merge_head_path = repo.path().join("MERGE_HEAD");
+            let merge_heads = std::fs::read_to_string(&merge_head_path)
+                .map_err(|e| CoreError::io(&merge_head_path, e))?;
+            for line in merge_heads.lines().filter(|l| !l.trim().is_empty()) {
+                parents.push(repo.find_commit(Oid::from_str(line.trim())?)?);
+            }
+        }
+        let parent_refs: Vec<&git2::Commit> = parents.iter().collect();
+        let id = repo.commit(
+            Some("HEAD"),
+            &signature,
+            &signature,
+            &message,
+            &tree,
+            &parent_refs,
+        )?;
+        if merging {
+            repo.cleanup_state()?;
+        }
+        id
+    };
+
+    Ok(commit_info(&repo.find_commit(id)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{commit_file, create_test_repo};
+    use std::fs;
+
+    fn stage(repo: &Repository, path: &str, content: &str) {
+        fs::write(repo.workdir().unwrap().join(path), content).unwrap();
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new(path)).unwrap();
+        index.write().unwrap();
+    }
+
+    #[cfg(unix)]
+    fn install_hook(repo: &Repository, name: &str, script: &str) {
+        use std::os::unix::fs::PermissionsExt;
+        let path = repo.path().join("hooks").join(name);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, script).unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+    }
+
+    #[test]
+    fn test_create_commit_in_empty_repo() {
+        let (temp_dir, repo) = create_test_repo();
+
+        stage(&repo, "file.txt", "one\n");
+        let info = create_commit(temp_dir.path(), "First\n\n# comment\n", false).unwrap();
+
+        assert_eq!(info.summary, "First");
+        assert_eq!(info.author.name, "Test User");
+        assert!(info.parents.is_empty());
+        assert_eq!(repo.head().unwrap().target().unwrap().to_string(), info.id);
+    }
+
+    #[test]
+    fn test_create_commit_rejects_empty_index_and_message() {
+        let (temp_dir, repo) = create_test_repo();
+
+        commit_file(&repo, "file.txt", "one\n", "Initial commit");
+        let result = create_commit(temp_dir.path(), "Nothing", false);
+        assert!(matches!(result, Err(CoreError::NothingToCommit)));
+
+        stage(&repo, "file.txt", "two\n");
+        let result = create_commit(temp_dir.path(), "  \n", false);
+        assert!(matches!(result, Err(CoreError::EmptyCommitMessage)));
+    }
+
+    #[test]
+    fn test_create_commit_amend() {
+        let (temp_dir, repo) = create_test_repo();
+
+        commit_file(&repo, "file.txt", "one\n", "Initial commit");
+        commit_file(&repo, "file.txt", "two\n", "Second");
+        let parent = repo
+            .head()
+            .unwrap()
+            .peel_to_commit()
+            .unwrap()
+            .parent_id(0)
+            .unwrap();
+
+        stage(&repo, "file.txt", "three\n");
+        let info = create_commit(temp_dir.path(), "Second, amended", true).unwrap();
+
+        assert_eq!(info.summary, "Second, amended");
+        assert_eq!(info.parents, vec![parent.to_string()]);
+        let tree = repo.head().unwrap().peel_to_tree().unwrap();
+        let blob = repo
+            .find_blob(tree.get_path(Path::new("file.txt")).unwrap().id())
+            .unwrap();
+        assert_eq!(blob.content(), b"three\n");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_create_commit_reports_failing_pre_commit_hook() {
+        let (temp_dir, repo) = create_test_repo();
+
+        commit_file(&repo, "file.txt", "one\n", "Initial commit");
+        install_hook(
+            &repo,
+            "pre-commit",
+            "#!/bin/sh\necho 'lint failed' >&2\nexit 3\n",
+        );
+        stage(&repo, "file.txt", "two\n");
+
+        let result = create_commit(temp_dir.path(), "Change", false);
+        let Err(CoreError::Hook {
+            hook,
+            exit_code,
+            output,
+        }) = result
+        else {
+            unreachable!()
+        };
+        assert_eq!(hook, "pre-commit");
+        assert_eq!(exit_code, Some(3));
+        assert_eq!(output, "lint failed");
+        assert_eq!(
+            repo.head().unwrap().peel_to_commit().unwrap().summary(),
+            Some("Initial commit")
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_create_commit_skips_hooks_when_nothing_to_commit() {
+        let (temp_dir, repo) = create_test_repo();
+
+        commit_file(&repo, "file.txt", "one\n", "Initial commit");
+        for hook in ["pre-commit", "commit-msg"] {
+            install_hook(&repo, hook, &format!("#!/bin/sh\ntouch {hook}.ran\n"));
+        }
+
+        let result = create_commit(temp_dir.path(), "Nothing", false);
+        assert!(matches!(result, Err(CoreError::NothingToCommit)));
+        assert!(!temp_dir.path().join("pre-commit.ran").exists());
+        assert!(!temp_dir.path().join("commit-msg.ran").exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_create_commit_uses_message_from_commit_msg_hook() {
+        let (temp_dir, repo) = create_test_repo();
+
+        install_hook(
+            &repo,
+            "commit-msg",
+            "#!/bin/sh\nprintf 'Rewritten\\n\\nTicket: 42\\n' > \"$1\"\n",
+        );
+        stage(&repo, "file.txt", "one\n");
+
+        let info = create_commit(temp_dir.path(), "Original", false).unwrap();
+        assert_eq!(info.summary, "Rewritten");
+        assert_eq!(info.body.as_deref(), Some("Ticket: 42"));
+    }
+}
@@ -0,0 +1,493 @@
+use crate::error::CoreError;
+use crate::fs::extension_to_lang;
+use crate::git::{
+    Snapshot, bytes_to_content, discover_repository, read_workdir_bytes, require_workdir,
+    validate_target_path,
+};
+use crate::staging::stage_file_with_repo;
+use crate::types::{
+    ConflictContents, ConflictRegion, ConflictResolution, DiffFile, DiffTarget, FileContent,
+    GitStatus,
+};
+use git2::{Index, Repository};
+use std::path::Path;
+
+const MARKER_LEN: usize = 7;
+
+fn marker_label(line: &str, marker: char) -> Option<&str> {
+    let prefix: String = std::iter::repeat_n(marker, MARKER_LEN).collect();
+    let rest = line.strip_prefix(prefix.as_str())?;
+    if rest.is_empty() {
+        return Some("");
+    }
+    rest.strip_prefix(' ').or_else(|| rest.strip_prefix('\t'))
+}
+
+enum Section {
+    Ours,
+    Base,
+    Theirs,
+}
+
+struct OpenConflict {
+    start_line: u32,
+    ours_label: String,
+    ours: Vec<String>,
+    base: Option<Vec<String>>,
+    theirs: Vec<String>,
+    section: Section,
+    raw: Vec<String>,
+}
+
+fn push_common(regions: &mut Vec<ConflictRegion>, line_no: u32, lines: Vec<String>) {
+    if let Some(ConflictRegion::Common {
+        end_line,
+        lines: existing,
+        ..
+    }) = regions.last_mut()
+    {
+        *end_line = line_no + u32::try_from(lines.len()).unwrap_or(0) - 1;
+        existing.extend(lines);
+        return;
+    }
+    regions.push(ConflictRegion::Common {
+        start_line: line_no,
+        end_line: line_no + u32::try_from(lines.len()).unwrap_or(0) - 1,
+        lines,
+    });
+}
+
+/// Splits a working-tree file into common text and `<<<<<<<`/`=======`/`>>>>>>>` conflict
+/// regions; an unterminated conflict is kept as common text.
+pub(crate) fn parse_conflict_regions(contents: &str) -> Vec<ConflictRegion> {
+    let mut regions = Vec::new();
+    let mut open: Option<OpenConflict> = None;
+
+    for (line, line_no) in contents.lines().zip(1u32..) {
+        let Some(conflict) = open.as_mut() else {
+            if let Some(label) = marker_label(line, '<') {
+                open = Some(OpenConflict {
+                    start_line: line_no,
+                    ours_label: label.to_owned(),
+                    ours: Vec::new(),
+                    base: None,
+                    theirs: Vec::new(),
+                    section: Section::Ours,
+                    raw: vec![line.to_owned()],
+                });
+            } else {
+                push_common(&mut regions, line_no, vec![line.to_owned()]);
+            }
+            continue;
+        };
+
+        conflict.raw.push(line.to_owned());
+        match conflict.section {
+            Section::Ours | Section::Base if line == "=".repeat(MARKER_LEN) => {
+                conflict.section = Section::Theirs;
+            }
+            Section::Ours if marker_label(line, '|').is_some() => {
+                conflict.base = Some(Vec::new());
+                conflict.section = Section::Base;
+            }
+            Section::Theirs if marker_label(line, '>').is_some() => {
+                let Some(conflict) = open.take() else {
+                    unreachable!()
+                };
+                regions.push(ConflictRegion::Conflict {
+                    start_line: conflict.start_line,
+                    end_line: line_no,
+                    ours_label: conflict.ours_label,
+                    theirs_label: marker_label(line, '>').unwrap_or_default().to_owned(),
+                    ours: conflict.ours,
+                    base: conflict.base,
+                    theirs: conflict.theirs,
+                });
+            }
+            Section::Ours => conflict.ours.push(line.to_owned()),
+            Section::Base => conflict.base.get_or_insert_default().push(line.to_owned()),
+            Section::Theirs => conflict.theirs.push(line.to_owned()),
+        }
+    }
+
+    if let Some(conflict) = open {
+        push_common(&mut regions, conflict.start_line, conflict.raw);
+    }
+    regions
+}
+
+fn require_conflict_stages(index: &Index, file_path: &str) -> Result<(), CoreError> {
+    if (1..=3).any(|stage| index.get_path(Path::new(file_path), stage).is_some()) {
+        return Ok(());
+    }
+    Err(CoreError::InvalidPath(format!(
+        "File is not conflicted: {file_path}"
+    )))
+}
+
+/// The terminator a raw line ends with, empty for a final line without one.
+fn line_ending(line: &str) -> &str {
+    if line.ends_with("\r\n") {
+        "\r\n"
+    } else if line.ends_with('\n') {
+        "\n"
+    } else {
+        ""
+    }
+}
+
+pub fn get_conflict_contents(
+    repo_path: &Path,
+    file_path: &str,
+) -> Result<ConflictContents, CoreError> {
+    let repo = discover_repository(repo_path)?;
+    get_conflict_contents_with_repo(&repo, file_path)
+}
+
+pub(crate) fn get_conflict_contents_with_repo(
+    repo: &Repository,
+    file_path: &str,
+) -> Result<ConflictContents, CoreError> {
+    validate_target_path(repo, file_path, &DiffTarget::Unstaged)?;
+
+    let index = repo.index()?;
+    let stage_content = |stage: i32| -> Result<Option<FileContent>, CoreError> {
+        index
+            .get_path(Path::new(file_path), stage)
+            .map(|entry| Ok(bytes_to_content(repo.find_blob(entry.id)?.content())))
+            .transpose()
+    };
+    require_conflict_stages(&index, file_path)?;
+    let base = stage_content(1)?;
+    let ours = stage_content(2)?;
+    let theirs = stage_content(3)?;
+
+    let working = read_workdir_bytes(repo, file_path)?.map(|b| bytes_to_content(&b));
+    let regions = match &working {
+        Some(FileContent::Text { contents }) => parse_conflict_regions(contents),
+        _ => Vec::new(),
+    };
+
+    let lang = extension_to_lang(file_path);
+    let file = |content: Option<FileContent>| DiffFile {
+        name: file_path.to_owned(),
+        lang: lang.clone(),
+        content,
+    };
+    Ok(ConflictContents {
+        base: file(base),
+        ours: file(ours),
+        theirs: file(theirs),
+        working: file(working),
+        regions,
+    })
+}
+
+/// Rewrites each conflict region of the working-tree file with its resolution, in order, and
+/// marks the path resolved. Fails if the number of regions no longer matches.
+pub fn resolve_conflicts(
+    repo_path: &Path,
+    file_path: &str,
+    resolutions: &[ConflictResolution],
+) -> Result<GitStatus, CoreError> {
+    let repo = discover_repository(repo_path)?;
+    resolve_conflicts_with_repo(&Snapshot::new(&repo), file_path, resolutions)
+}
+
+pub(crate) fn resolve_conflicts_with_repo(
+    repo: &Snapshot,
+    file_path: &str,
+    resolutions: &[ConflictResolution],
+) -> Result<GitStatus, CoreError> {
+    validate_target_path(repo, file_path, &DiffTarget::Unstaged)?;
+    require_conflict_stages(&repo.index()?, file_path)?;
+
+    let bytes = read_workdir_bytes(repo, file_path)?.unwrap_or_default();
+    let FileContent::Text { contents } = bytes_to_content(&bytes) else {
+        return Err(CoreError::InvalidPath(format!(
+            "Cannot resolve conflicts in a binary file: {file_path}"
+        )));
+    };
+    let regions = parse_conflict_regions(&contents);
+    let conflict_count = regions
+        .iter()
+        .filter(|r| matches!(r, ConflictRegion::Conflict { .. }))
+        .count();
+    if conflict_count != resolutions.len() {
+        return Err(CoreError::StaleSelection(file_path.to_owned()));
+    }
+
+    // Regions only carry line text, so copy kept lines from the raw file to preserve each
+    // line's own terminator.
+    let raw: Vec<&str> = contents.split_inclusive('\n').collect();
+    let raw_lines = |start: usize, len: usize| raw[start..start + len].iter().copied();
+    let mut resolutions = resolutions.iter();
+    let mut resolved = String::with_capacity(contents.len());
+    for region in regions {
+        match region {
+            ConflictRegion::Common {
+                start_line,
+                end_line,
+                ..
+            } => {
+                let start = start_line as usize - 1;
+                resolved.extend(raw_lines(start, end_line as usize - start));
+            }
+            ConflictRegion::Conflict {
+                start_line,
+                end_line,
+                ours,
+                theirs,
+                ..
+            } => {
+                let resolution = resolutions
+                    .next()
+                    .ok_or_else(|| CoreError::StaleSelection(file_path.to_owned()))?;
+                let ours_start = start_line as usize;
+                let theirs_start = end_line as usize - 1 - theirs.len();
+                match resolution {
+                    ConflictResolution::Ours => resolved.extend(raw_lines(ours_start, ours.len())),
+                    ConflictResolution::Theirs => {
+                        resolved.extend(raw_lines(theirs_start, theirs.len()));
+                    }
+                    ConflictResolution::Both => {
+                        resolved.extend(raw_lines(ours_start, ours.len()));
+                        resolved.extend(raw_lines(theirs_start, theirs.len()));
+                    }
+                    ConflictResolution::Custom { lines: custom } => {
+                        let newline = line_ending(raw[start_line as usize - 1]);
+                        for line in custom {
+                            resolved.push_str(line);
+                            resolved.push_str(newline);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    if !contents.ends_with('\n') {
+        let trimmed = resolved.len() - line_ending(&resolved).len();
+        resolved.truncate(trimmed);
+    }
+    let full_path = require_workdir(repo)?.join(file_path);
+    std::fs::write(&full_path, resolved).map_err(|e| CoreError::io(&full_path, e))?;
+
+    stage_file_with_repo(repo, file_path)
+}
+
+/// Marks a hand-edited conflicted path as resolved by adding its working-tree state to the index.
+pub fn mark_resolved(repo_path: &Path, file_path: &str) -> Result<GitStatus, CoreError> {
+    let repo = discover_repository(repo_path)?;
+    stage_file_with_repo(&Snapshot::new(&repo), file_path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{commit_file, create_test_repo};
+    use std::fs;
+
+    fn merge_conflicting_branch(repo: &Repository) {
+        commit_file(repo, "file.txt", "top\nbase\nbottom\n", "Base");
+        let head_name = repo.head().unwrap().name().unwrap().to_owned();
+        let base = repo.head().unwrap().peel_to_commit().unwrap();
+        repo.branch("other", &base, false).unwrap();
+
+        commit_file(repo, "file.txt", "top\nours\nbottom\n", "Ours");
+        repo.set_head("refs/heads/other").unwrap();
+        repo.checkout_head(Some(git2::build::CheckoutBuilder::new().force()))
+            .unwrap();
+        commit_file(repo, "file.txt", "top\ntheirs\nbottom\n", "Theirs");
+        let theirs = repo.head().unwrap().peel_to_commit().unwrap();
+
+        repo.set_head(&head_name).unwrap();
+        repo.checkout_head(Some(git2::build::CheckoutBuilder::new().force()))
+            .unwrap();
+
+        let annotated = repo.find_annotated_commit(theirs.id()).unwrap();
+        repo.merge(&[&annotated], None, None).unwrap();
+    }
+
+    #[test]
+    fn test_parse_conflict_regions() {
+        let contents = "a\n<<<<<<< HEAD\nours\n=======\ntheirs\n>>>>>>> branch\nb\nc\n";
+
+        assert_eq!(
+            parse_conflict_regions(contents),
+            vec![
+                ConflictRegion::Common {
+                    start_line: 1,
+                    end_line: 1,
+                    lines: vec!["a".to_owned()],
+                },
+                ConflictRegion::Conflict {
+                    start_line: 2,
+                    end_line: 6,
+                    ours_label: "HEAD".to_owned(),
+                    theirs_label: "branch".to_owned(),
+                    ours: vec!["ours".to_owned()],
+                    base: None,
+                    theirs: vec!["theirs".to_owned()],
+                },
+                ConflictRegion::Common {
+                    start_line: 7,
+                    end_line: 8,
+                    lines: vec!["b".to_owned(), "c".to_owned()],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_diff3_and_unterminated_conflicts() {
+        let contents =
+            "<<<<<<< ours\nx\n||||||| base\ny\n=======\nz\n>>>>>>> theirs\n<<<<<<< a\nq\n";
+        let regions = parse_conflict_regions(contents);
+
+        assert_eq!(regions.len(), 2);
+        let ConflictRegion::Conflict { base, .. } = &regions[0] else {
+            unreachable!()
+        };
+        assert_eq!(base.as_deref(), Some(&["y".to_owned()][..]));
+        assert_eq!(
+            regions[1],
+            ConflictRegion::Common {
+                start_line: 8,
+                end_line: 9,
+                lines: vec!["<<<<<<< a".to_owned(), "q".to_owned()],
+            }
+        );
+    }
+
+    #[test]
+    fn test_get_conflict_contents_after_merge() {
+        let (temp_dir, repo) = create_test_repo();
+        merge_conflicting_branch(&repo);
+
+        let contents = get_conflict_contents(temp_dir.path(), "file.txt").unwrap();
+        let text = |file: &DiffFile| match &file.content {
+            Some(FileContent::Text { contents }) => contents.clone(),
+            _ => unreachable!(),
+        };
+        assert_eq!(text(&contents.base), "top\nbase\nbottom\n");
+        assert_eq!(text(&contents.ours), "top\nours\nbottom\n");
+        assert_eq!(text(&contents.theirs), "top\ntheirs\nbottom\n");
+
+        let conflicts: Vec<_> = contents
+            .regions
+            .iter()
+            .filter_map(|r| match r {
+                ConflictRegion::Conflict { ours, theirs, .. } => Some((ours, theirs)),
+                ConflictRegion::Common { .. } => None,
+            })
+            .collect();
+        assert_eq!(
+            conflicts,
+            vec![(&vec!["ours".to_owned()], &vec!["theirs".to_owned()])]
+        );
+    }
+
+    #[test]
+    fn test_get_conflict_contents_rejects_clean_file() {
+        let (temp_dir, repo) = create_test_repo();
+        commit_file(&repo, "file.txt", "one\n", "Initial commit");
+
+        let result = get_conflict_contents(temp_dir.path(), "file.txt");
+        assert!(matches!(result, Err(CoreError::InvalidPath(_))));
+    }
+
+    #[test]
+    fn test_resolve_conflicts_writes_and_marks_resolved() {
+        let (temp_dir, repo) = create_test_repo();
+        merge_conflicting_branch(&repo);
+
+        let status =
+            resolve_conflicts(temp_dir.path(), "file.txt", &[ConflictResolution::Both]).unwrap();
+
+        assert_eq!(
+            fs::read_to_string(temp_dir.path().join("file.txt")).unwrap(),
+            "top\nours\ntheirs\nbottom\n"
+        );
+        let mut index = repo.index().unwrap();
+        index.read(true).unwrap();
+        assert!(!index.has_conflicts());
+        assert!(status.unstaged.iter().all(|e| e.path != "file.txt"));
+    }
+
+    #[test]
+    fn test_resolve_conflicts_with_custom_lines() {
+        let (temp_dir, repo) = create_test_repo();
+        merge_conflicting_branch(&repo);
+
+        let custom = ConflictResolution::Custom {
+            lines: vec!["merged".to_owned()],
+        };
+        resolve_conflicts(temp_dir.path(), "file.txt", &[custom]).unwrap();
+
+        assert_eq!(
+            fs::read_to_string(temp_dir.path().join("file.txt")).unwrap(),
+            "top\nmerged\nbottom\n"
+        );
+    }
+
+    #[test]
+    fn test_resolve_conflicts_rejects_mismatched_resolutions() {
+        let (temp_dir, repo) = create_test_repo();
+        merge_conflicting_branch(&repo);
+
+        let result = resolve_conflicts(
+            temp_dir.path(),
+            "file.txt",
+            &[ConflictResolution::Ours, ConflictResolution::Theirs],
+        );
+        assert!(matches!(result, Err(CoreError::StaleSelection(_))));
+        assert!(repo.index().unwrap().has_conflicts());
+    }
+
+    #[test]
+    fn test_resolve_conflicts_keeps_mixed_line_endings() {
+        let (temp_dir, repo) = create_test_repo();
+        merge_conflicting_branch(&repo);
+
+        let working = "top\r\n<<<<<<< HEAD\nours\r\n=======\r\ntheirs\n>>>>>>> other\r\nbottom\n";
+        fs::write(temp_dir.path().join("file.txt"), working).unwrap();
+        resolve_conflicts(temp_dir.path(), "file.txt", &[ConflictResolution::Both]).unwrap();
+        assert_eq!(
+            fs::read_to_string(temp_dir.path().join("file.txt")).unwrap(),
+            "top\r\nours\r\ntheirs\nbottom\n"
+        );
+    }
+
+    #[test]
+    fn test_resolve_conflicts_rejects_clean_file() {
+        let (temp_dir, repo) = create_test_repo();
+        let contents = "<<<<<<< HEAD\nours\n=======\ntheirs\n>>>>>>> other\n";
+        commit_file(&repo, "file.txt", contents, "Initial commit");
+
+        let result = resolve_conflicts(temp_dir.path(), "file.txt", &[ConflictResolution::Ours]);
+        assert!(matches!(result, Err(CoreError::InvalidPath(_))));
+        assert_eq!(
+            fs::read_to_string(temp_dir.path().join("file.txt")).unwrap(),
+            contents
+        );
+    }
+
+    #[test]
+    fn test_mark_resolved_after_hand_edit() {
+        let (temp_dir, repo) = create_test_repo();
+        merge_conflicting_branch(&repo);
+
+        fs::write(temp_dir.path().join("file.txt"), "hand edited\n").unwrap();
+        mark_resolved(temp_dir.path(), "file.txt").unwrap();
+
+        let mut index = repo.index().unwrap();
+        index.read(true).unwrap();
+        assert!(!index.has_conflicts());
+        let entry = index.get_path(Path::new("file.txt"), 0).unwrap();
+        assert_eq!(
+            repo.find_blob(entry.id).unwrap().content(),
+            b"hand edited\n"
+        );
+    }
+}
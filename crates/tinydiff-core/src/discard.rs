@@ -0,0 +1,387 @@
+use crate::error::CoreError;
+use crate::git::{
+    Snapshot, discover_repository, get_status_with_repo, require_workdir, validate_target_path,
+};
+use crate::types::{DiffTarget, DiscardRecord, GitStatus};
+use fs2::FileExt;
+use git2::build::CheckoutBuilder;
+use git2::{ErrorCode, Oid, Repository, Status};
+use std::fs::{self, File};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const MAX_DISCARDS: usize = 50;
+
+fn discard_ref(id: &str) -> String {
+    format!("refs/tinydiff/discards/{id}")
+}
+
+fn invalid_data(path: &Path, e: serde_json::Error) -> CoreError {
+    CoreError::io(
+        path,
+        std::io::Error::new(std::io::ErrorKind::InvalidData, e),
+    )
+}
+
+fn lock_discards(dir_path: &Path) -> Result<File, CoreError> {
+    fs::create_dir_all(dir_path).map_err(|e| CoreError::io(dir_path, e))?;
+    let lock_path = dir_path.join("discards.lock");
+    let lock_file = File::create(&lock_path).map_err(|e| CoreError::io(&lock_path, e))?;
+    lock_file
+        .lock_exclusive()
+        .map_err(|e| CoreError::io(&lock_path, e))?;
+    Ok(lock_file)
+}
+
+fn read_records(dir_path: &Path) -> Result<Vec<DiscardRecord>, CoreError> {
+    let path = dir_path.join("discards.json");
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let contents = fs::read_to_string(&path).map_err(|e| CoreError::io(&path, e))?;
+    serde_json::from_str(&contents).map_err(|e| invalid_data(&path, e))
+}
+
+fn write_records(dir_path: &Path, records: &[DiscardRecord]) -> Result<(), CoreError> {
+    let file_path = dir_path.join("discards.json");
+    let temp_path = dir_path.join("discards.json.tmp");
+
+    let contents =
+        serde_json::to_string_pretty(records).map_err(|e| invalid_data(&file_path, e))?;
+    fs::write(&temp_path, &contents).map_err(|e| CoreError::io(&temp_path, e))?;
+    if let Err(e) = fs::rename(&temp_path, &file_path) {
+        let _ = fs::remove_file(&temp_path);
+        return Err(CoreError::io(&file_path, e));
+    }
+    Ok(())
+}
+
+/// Records `contents` as the state of `file_path` at the front of `records`, pruning the oldest.
+fn push_snapshot(
+    repo: &Repository,
+    file_path: &str,
+    contents: Option<&[u8]>,
+    partial: bool,
+    records: &mut Vec<DiscardRecord>,
+) -> Result<DiscardRecord, CoreError> {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    let id = format!("{}", now.as_nanos());
+    let blob_id = match contents {
+        Some(bytes) => {
+            let blob_id = repo.blob(bytes)?;
+            repo.reference(&discard_ref(&id), blob_id, true, "tinydiff: discard")?;
+            Some(blob_id.to_string())
+        }
+        None => None,
+    };
+
+    let record = DiscardRecord {
+        id,
+        file_path: file_path.to_owned(),
+        blob_id,
+        partial,
+        created_at: i64::try_from(now.as_secs()).unwrap_or(i64::MAX),
+    };
+
+    records.insert(0, record.clone());
+    for pruned in records.drain(MAX_DISCARDS.min(records.len())..) {
+        if let Ok(mut reference) = repo.find_reference(&discard_ref(&pruned.id)) {
+            reference.delete()?;
+        }
+    }
+
+    Ok(record)
+}
+
+fn read_working_file(full_path: &Path) -> Result<Option<Vec<u8>>, CoreError> {
+    match fs::read(full_path) {
+        Ok(bytes) => Ok(Some(bytes)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(CoreError::io(full_path, e)),
+    }
+}
+
+/// Whether the working-tree file differs from the index, i.e. would be lost if overwritten.
+fn has_unstaged_changes(repo: &Repository, file_path: &str) -> Result<bool, CoreError> {
+    match repo.status_file(Path::new(file_path)) {
+        Ok(status) => Ok(status.intersects(
+            Status::WT_NEW | Status::WT_MODIFIED | Status::WT_DELETED | Status::WT_TYPECHANGE,
+        )),
+        Err(e) if e.code() == ErrorCode::NotFound => Ok(false),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Stores the current working-tree contents of `file_path` so a following discard can be undone.
+pub(crate) fn snapshot(
+    repo: &Repository,
+    file_path: &str,
+    partial: bool,
+) -> Result<DiscardRecord, CoreError> {
+    validate_target_path(repo, file_path, &DiffTarget::Unstaged)?;
+    let workdir = require_workdir(repo)?;
+    let dir_path = workdir.join(".tinydiff");
+    let contents = read_working_file(&workdir.join(file_path))?;
+
+    let _lock = lock_discards(&dir_path)?;
+    let mut records = read_records(&dir_path)?;
+    let record = push_snapshot(repo, file_path, contents.as_deref(), partial, &mut records)?;
+    write_records(&dir_path, &records)?;
+
+    Ok(record)
+}
+
+/// Discards all working-tree changes to `file_path`, deleting it if untracked, after taking a
+/// snapshot that `restore_discard` can bring back.
+pub fn discard_file(repo_path: &Path, file_path: &str) -> Result<DiscardRecord, CoreError> {
+    let repo = discover_repository(repo_path)?;
+    discard_file_with_repo(&repo, file_path)
+}
+
+pub(crate) fn discard_file_with_repo(
+    repo: &Repository,
+    file_path: &str,
+) -> Result<DiscardRecord, CoreError> {
+    let record = snapshot(repo, file_path, false)?;
+
+    if repo.index()?.get_path(Path::new(file_path), 0).is_some() {
+        let mut checkout = CheckoutBuilder::new();
+        checkout
+            .force()
+            .disable_pathspec_match(true)
+            .path(file_path);
+        repo.checkout_index(None, Some(&mut checkout))?;
+    } else {
+        let full_path = require_workdir(repo)?.join(file_path);
+        fs::remove_file(&full_path).map_err(|e| CoreError::io(&full_path, e))?;
+    }
+
+    Ok(record)
+}
+
+/// Recent discards, newest first.
+pub fn list_discards(repo_path: &Path) -> Result<Vec<DiscardRecord>, CoreError> {
+    let repo = discover_repository(repo_path)?;
+    list_discards_with_repo(&repo)
+}
+
+pub(crate) fn list_discards_with_repo(repo: &Repository) -> Result<Vec<DiscardRecord>, CoreError> {
+    read_records(&require_workdir(repo)?.join(".tinydiff"))
+}
+
+/// Writes a discarded snapshot back to the working tree and forgets it. Unstaged changes the
+/// restore overwrites are snapshotted first, so they can be restored in turn.
+pub fn restore_discard(repo_path: &Path, discard_id: &str) -> Result<GitStatus, CoreError> {
+    let repo = discover_repository(repo_path)?;
+    restore_discard_with_repo(&Snapshot::new(&repo), discard_id)
+}
+
+pub(crate) fn restore_discard_with_repo(
+    repo: &Snapshot,
+    discard_id: &str,
+) -> Result<GitStatus, CoreError> {
+    let workdir = require_workdir(repo)?;
+    let dir_path = workdir.join(".tinydiff");
+    let _lock = lock_discards(&dir_path)?;
+    let mut records = read_records(&dir_path)?;
+    let position = records
+        .iter()
+        .position(|r| r.id == discard_id)
+        .ok_or_else(|| CoreError::InvalidPath(format!("Unknown discard: {discard_id}")))?;
+    let record = records.remove(position);
+    validate_target_path(repo, &record.file_path, &DiffTarget::Unstaged)?;
+
+    let full_path = workdir.join(&record.file_path);
+    let restored = record
+        .blob_id
+        .as_deref()
+        .map(|id| -> Result<Vec<u8>, CoreError> {
+            Ok(repo.find_blob(Oid::from_str(id)?)?.content().to_vec())
+        })
+        .transpose()?;
+    let current = read_working_file(&full_path)?;
+    if current != restored && has_unstaged_changes(repo, &record.file_path)? {
+        push_snapshot(
+            repo,
+            &record.file_path,
+            current.as_deref(),
+            false,
+            &mut records,
+        )?;
+    }
+
+    match &restored {
+        Some(contents) => {
+            if let Some(parent) = full_path.parent() {
+                fs::create_dir_all(parent).map_err(|e| CoreError::io(parent, e))?;
+            }
+            fs::write(&full_path, contents).map_err(|e| CoreError::io(&full_path, e))?;
+        }
+        None => match fs::remove_file(&full_path) {
+            Ok(()) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(CoreError::io(&full_path, e)),
+        },
+    }
+
+    if let Ok(mut reference) = repo.find_reference(&discard_ref(&record.id)) {
+        reference.delete()?;
+    }
+    write_records(&dir_path, &records)?;
+
+    get_status_with_repo(repo)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::staging::apply_selection;
+    use crate::test_support::{commit_file, create_test_repo};
+    use crate::types::{DiffContent, DiffOptions, DiffTarget, HunkSelection, PatchAction};
+
+    #[test]
+    fn test_discard_file_and_restore() {
+        let (temp_dir, repo) = create_test_repo();
+        let file = temp_dir.path().join("file.txt");
+
+        commit_file(&repo, "file.txt", "one\n", "Initial commit");
+        fs::write(&file, "edited\n").unwrap();
+
+        let record = discard_file(temp_dir.path(), "file.txt").unwrap();
+        assert_eq!(fs::read_to_string(&file).unwrap(), "one\n");
+        assert!(!record.partial);
+        let reference = repo.find_reference(&discard_ref(&record.id)).unwrap();
+        assert_eq!(reference.target().map(|id| id.to_string()), record.blob_id);
+        assert_eq!(
+            list_discards(temp_dir.path()).unwrap(),
+            vec![record.clone()]
+        );
+
+        restore_discard(temp_dir.path(), &record.id).unwrap();
+        assert_eq!(fs::read_to_string(&file).unwrap(), "edited\n");
+        assert!(list_discards(temp_dir.path()).unwrap().is_empty());
+        assert!(repo.find_reference(&discard_ref(&record.id)).is_err());
+    }
+
+    #[test]
+    fn test_discard_untracked_and_deleted_files() {
+        let (temp_dir, repo) = create_test_repo();
+
+        commit_file(&repo, "tracked.txt", "one\n", "Initial commit");
+        fs::remove_file(temp_dir.path().join("tracked.txt")).unwrap();
+        fs::write(temp_dir.path().join("new.txt"), "new\n").unwrap();
+
+        let deleted = discard_file(temp_dir.path(), "tracked.txt").unwrap();
+        let untracked = discard_file(temp_dir.path(), "new.txt").unwrap();
+        assert!(deleted.blob_id.is_none());
+        assert!(temp_dir.path().join("tracked.txt").exists());
+        assert!(!temp_dir.path().join("new.txt").exists());
+
+        let listed: Vec<String> = list_discards(temp_dir.path())
+            .unwrap()
+            .into_iter()
+            .map(|r| r.file_path)
+            .collect();
+        assert_eq!(listed, vec!["new.txt", "tracked.txt"]);
+
+        restore_discard(temp_dir.path(), &untracked.id).unwrap();
+        restore_discard(temp_dir.path(), &deleted.id).unwrap();
+        assert!(!temp_dir.path().join("tracked.txt").exists());
+        assert_eq!(
+            fs::read_to_string(temp_dir.path().join("new.txt")).unwrap(),
+            "new\n"
+        );
+    }
+
+    #[test]
+    fn test_hunk_discard_is_recoverable() {
+        let (temp_dir, repo) = create_test_repo();
+        let file = temp_dir.path().join("file.txt");
+
+        commit_file(&repo, "file.txt", "a\nb\n", "Initial commit");
+        fs::write(&file, "a\nB\n").unwrap();
+
+        let diff = crate::git::get_file_diff(
+            temp_dir.path(),
+            "file.txt",
+            &DiffTarget::Unstaged,
+            &DiffOptions::default(),
+        )
+        .unwrap();
+        let DiffContent::Text { hunks } = diff.content else {
+            unreachable!()
+        };
+        let selection = HunkSelection {
+            hunk: hunks[0].clone(),
+            lines: None,
+        };
+        apply_selection(
+            temp_dir.path(),
+            "file.txt",
+            PatchAction::Discard,
+            &[selection],
+            &DiffOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(fs::read_to_string(&file).unwrap(), "a\nb\n");
+
+        let records = list_discards(temp_dir.path()).unwrap();
+        assert_eq!(records.len(), 1);
+        assert!(records[0].partial);
+
+        restore_discard(temp_dir.path(), &records[0].id).unwrap();
+        assert_eq!(fs::read_to_string(&file).unwrap(), "a\nB\n");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_discard_and_restore_reject_symlinked_directory() {
+        let (temp_dir, repo) = create_test_repo();
+        let outside = tempfile::TempDir::new().unwrap();
+        let secret = outside.path().join("file.txt");
+
+        commit_file(&repo, "other.txt", "x\n", "Initial commit");
+        fs::create_dir(temp_dir.path().join("dir")).unwrap();
+        fs::write(temp_dir.path().join("dir/file.txt"), "mine\n").unwrap();
+        let record = discard_file(temp_dir.path(), "dir/file.txt").unwrap();
+
+        fs::remove_dir(temp_dir.path().join("dir")).unwrap();
+        std::os::unix::fs::symlink(outside.path(), temp_dir.path().join("dir")).unwrap();
+        fs::write(&secret, "secret\n").unwrap();
+
+        let result = discard_file(temp_dir.path(), "dir/file.txt");
+        assert!(matches!(result, Err(CoreError::InvalidPath(_))));
+        let result = restore_discard(temp_dir.path(), &record.id);
+        assert!(matches!(result, Err(CoreError::InvalidPath(_))));
+        assert_eq!(fs::read_to_string(&secret).unwrap(), "secret\n");
+    }
+
+    #[test]
+    fn test_restore_unknown_discard() {
+        let (temp_dir, _repo) = create_test_repo();
+
+        let result = restore_discard(temp_dir.path(), "missing");
+        assert!(matches!(result, Err(CoreError::InvalidPath(_))));
+    }
+
+    #[test]
+    fn test_restore_snapshots_overwritten_changes() {
+        let (temp_dir, repo) = create_test_repo();
+        let file = temp_dir.path().join("file.txt");
+
+        commit_file(&repo, "file.txt", "one\n", "Initial commit");
+        fs::write(&file, "edited\n").unwrap();
+        let record = discard_file(temp_dir.path(), "file.txt").unwrap();
+        fs::write(&file, "later\n").unwrap();
+
+        restore_discard(temp_dir.path(), &record.id).unwrap();
+        assert_eq!(fs::read_to_string(&file).unwrap(), "edited\n");
+
+        let records = list_discards(temp_dir.path()).unwrap();
+        assert_eq!(records.len(), 1);
+        restore_discard(temp_dir.path(), &records[0].id).unwrap();
+        assert_eq!(fs::read_to_string(&file).unwrap(), "later\n");
+    }
+}
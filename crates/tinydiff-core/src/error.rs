@@ -16,6 +16,25 @@ pub enum CoreError {
     #[error("invalid path: {0}")]
     InvalidPath(String),
 
+    #[error("'{0}' changed since the diff was computed")]
+    StaleSelection(String),
+
+    #[error("{hook} hook failed: {output}")]
+    Hook {
+        hook: String,
+        exit_code: Option<i32>,
+        output: String,
+    },
+
+    #[error("nothing to commit")]
+    NothingToCommit,
+
+    #[error("commit message is empty")]
+    EmptyCommitMessage,
+
+    #[error("bare repository has no working tree")]
+    BareRepository,
+
     #[error("task panicked: {0}")]
     TaskPanic(#[from] tokio::task::JoinError),
 }
@@ -1,11 +1,24 @@
 use crate::error::CoreError;
 use crate::fs::extension_to_lang;
+use crate::history::signature_info;
+use crate::inline_diff;
+use crate::session::BlobCache;
 use crate::types::{
-    DiffContent, DiffFile, DiffHunk, DiffLine, DiffTarget, FileContent, FileDiff, FileEntry,
-    FileEntryKind, GitFileContents, GitStatus, LineChangeType,
+    ChangeSummary, ChangesetDiff, ChangesetFile, DiffAlgorithm, DiffContent, DiffFile, DiffHunk,
+    DiffLine, DiffOptions, DiffSide, DiffStats, DiffTarget, FileContent, FileDiff, FileEntry,
+    FileEntryKind, GitFileContents, GitStatus, HeadState, LineBlame, LineChangeType,
+    RenameDetection, RepoOperation, UpstreamStatus, WhitespaceMode,
 };
-use git2::{DiffLineType, DiffOptions, Repository, Status, StatusOptions};
-use std::path::Path;
+use git2::{
+    BlameOptions, BranchType, Commit, Delta, Diff, DiffFormat, DiffLineType, ErrorCode, FileMode,
+    ObjectType, Oid, Repository, RepositoryState, Status, StatusOptions, SubmoduleIgnore,
+    SubmoduleStatus, Tree,
+};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
 pub fn open_repository(path: &Path) -> Result<Repository, CoreError> {
     Repository::open(path).map_err(CoreError::from)
@@ -15,6 +28,47 @@ pub fn discover_repository(path: &Path) -> Result<Repository, CoreError> {
     Repository::discover(path).map_err(CoreError::from)
 }
 
+pub(crate) fn require_workdir(repo: &Repository) -> Result<&Path, CoreError> {
+    repo.workdir().ok_or(CoreError::BareRepository)
+}
+
+/// A repository with HEAD's tree resolved once, so every diff built from it compares against
+/// the same commit. Dereferences to the repository.
+pub(crate) struct Snapshot<'r> {
+    repo: &'r Repository,
+    head_tree: Option<Tree<'r>>,
+}
+
+impl<'r> Snapshot<'r> {
+    pub(crate) fn new(repo: &'r Repository) -> Self {
+        let head_tree = repo.head().ok().and_then(|h| h.peel_to_tree().ok());
+        Self::with_head_tree(repo, head_tree)
+    }
+
+    pub(crate) fn with_head_tree(repo: &'r Repository, head_tree: Option<Tree<'r>>) -> Self {
+        Self { repo, head_tree }
+    }
+
+    pub(crate) fn head_tree(&self) -> Option<&Tree<'r>> {
+        self.head_tree.as_ref()
+    }
+}
+
+impl Deref for Snapshot<'_> {
+    type Target = Repository;
+
+    fn deref(&self) -> &Repository {
+        self.repo
+    }
+}
+
+fn ensure_target_available(repo: &Repository, target: &DiffTarget) -> Result<(), CoreError> {
+    if repo.is_bare() && target.uses_working_tree() {
+        return Err(CoreError::BareRepository);
+    }
+    Ok(())
+}
+
 #[derive(Clone, Copy)]
 enum BasicStatus {
     Added,
@@ -30,12 +84,12 @@ fn status_to_basic(status: Status, staged: bool) -> Option<BasicStatus> {
     if staged {
         if status.contains(Status::INDEX_NEW) {
             Some(BasicStatus::Added)
+        } else if status.contains(Status::INDEX_RENAMED) {
+            Some(BasicStatus::Renamed)
         } else if status.contains(Status::INDEX_MODIFIED) {
             Some(BasicStatus::Modified)
         } else if status.contains(Status::INDEX_DELETED) {
             Some(BasicStatus::Deleted)
-        } else if status.contains(Status::INDEX_RENAMED) {
-            Some(BasicStatus::Renamed)
         } else if status.contains(Status::INDEX_TYPECHANGE) {
             Some(BasicStatus::Typechange)
         } else {
@@ -43,12 +97,12 @@ fn status_to_basic(status: Status, staged: bool) -> Option<BasicStatus> {
         }
     } else if status.contains(Status::WT_NEW) {
         Some(BasicStatus::Untracked)
+    } else if status.contains(Status::WT_RENAMED) {
+        Some(BasicStatus::Renamed)
     } else if status.contains(Status::WT_MODIFIED) {
         Some(BasicStatus::Modified)
     } else if status.contains(Status::WT_DELETED) {
         Some(BasicStatus::Deleted)
-    } else if status.contains(Status::WT_RENAMED) {
-        Some(BasicStatus::Renamed)
     } else if status.contains(Status::WT_TYPECHANGE) {
         Some(BasicStatus::Typechange)
     } else if status.contains(Status::CONFLICTED) {
@@ -58,13 +112,14 @@ fn status_to_basic(status: Status, staged: bool) -> Option<BasicStatus> {
     }
 }
 
-fn basic_to_kind(basic: BasicStatus, old_path: Option<String>) -> FileEntryKind {
+fn basic_to_kind(basic: BasicStatus, old_path: Option<String>, similarity: u16) -> FileEntryKind {
     match basic {
         BasicStatus::Added => FileEntryKind::Added,
         BasicStatus::Modified => FileEntryKind::Modified,
         BasicStatus::Deleted => FileEntryKind::Deleted,
         BasicStatus::Renamed => FileEntryKind::Renamed {
             old_path: old_path.unwrap_or_default(),
+            similarity,
         },
         BasicStatus::Untracked => FileEntryKind::Untracked,
         BasicStatus::Typechange => FileEntryKind::Typechange,
@@ -72,22 +127,7 @@ fn basic_to_kind(basic: BasicStatus, old_path: Option<String>) -> FileEntryKind
     }
 }
 
-fn blob_to_content(blob: Option<git2::Blob>) -> Option<FileContent> {
-    blob.map(|b| {
-        let content = b.content();
-        if content.contains(&0) {
-            FileContent::Binary {
-                size: content.len() as u64,
-            }
-        } else {
-            FileContent::Text {
-                contents: String::from_utf8_lossy(content).into_owned(),
-            }
-        }
-    })
-}
-
-fn bytes_to_content(bytes: &[u8]) -> FileContent {
+pub(crate) fn bytes_to_content(bytes: &[u8]) -> FileContent {
     if bytes.contains(&0) {
         FileContent::Binary {
             size: bytes.len() as u64,
@@ -99,50 +139,656 @@ fn bytes_to_content(bytes: &[u8]) -> FileContent {
     }
 }
 
+fn resolve_tree<'r>(repo: &'r Repository, spec: &str) -> Result<Tree<'r>, CoreError> {
+    Ok(repo.revparse_single(spec)?.peel_to_tree()?)
+}
+
+fn range_trees<'r>(
+    repo: &'r Repository,
+    from: Option<&str>,
+    to: &str,
+) -> Result<(Option<Tree<'r>>, Tree<'r>), CoreError> {
+    let old_tree = if let Some(spec) = from {
+        Some(resolve_tree(repo, spec)?)
+    } else {
+        let commit = repo.revparse_single(to)?.peel_to_commit()?;
+        commit.parents().next().map(|p| p.tree()).transpose()?
+    };
+    Ok((old_tree, resolve_tree(repo, to)?))
+}
+
+fn merge_base_trees<'r>(
+    repo: &'r Repository,
+    base: &str,
+    head: &str,
+) -> Result<(Option<Tree<'r>>, Tree<'r>), CoreError> {
+    let base_commit = repo.revparse_single(base)?.peel_to_commit()?;
+    let head_commit = repo.revparse_single(head)?.peel_to_commit()?;
+    let merge_base = repo.merge_base(base_commit.id(), head_commit.id())?;
+    let base_tree = repo.find_commit(merge_base)?.tree()?;
+    Ok((Some(base_tree), head_commit.tree()?))
+}
+
+/// The commit a stash was created on, the stashed working tree, and its untracked files.
+struct StashTrees<'r> {
+    base: Commit<'r>,
+    stash: Tree<'r>,
+    untracked: Option<Tree<'r>>,
+}
+
+fn stash_trees(repo: &Repository, index: u32) -> Result<StashTrees<'_>, CoreError> {
+    let stash = repo
+        .revparse_single(&format!("stash@{{{index}}}"))?
+        .peel_to_commit()?;
+    let untracked = stash.parent(2).ok().map(|c| c.tree()).transpose()?;
+    Ok(StashTrees {
+        base: stash.parent(0)?,
+        stash: stash.tree()?,
+        untracked,
+    })
+}
+
+fn tree_blob_id(tree: Option<&Tree>, file_path: &str) -> Option<Oid> {
+    tree.and_then(|tree| tree.get_path(Path::new(file_path)).ok())
+        .filter(|entry| entry.kind() == Some(ObjectType::Blob))
+        .map(|entry| entry.id())
+}
+
+fn include_untracked_content(opts: &mut git2::DiffOptions) {
+    opts.include_untracked(true)
+        .recurse_untracked_dirs(true)
+        .show_untracked_content(true);
+}
+
+pub(crate) fn apply_diff_options(opts: &mut git2::DiffOptions, options: &DiffOptions) {
+    opts.context_lines(options.context_lines)
+        .interhunk_lines(options.interhunk_lines)
+        .ignore_whitespace(options.whitespace == WhitespaceMode::IgnoreAll)
+        .ignore_whitespace_change(options.whitespace == WhitespaceMode::IgnoreChange)
+        .ignore_whitespace_eol(options.whitespace == WhitespaceMode::IgnoreEol)
+        .indent_heuristic(options.indent_heuristic)
+        .minimal(options.algorithm == DiffAlgorithm::Minimal)
+        .patience(options.algorithm == DiffAlgorithm::Patience);
+}
+
+pub(crate) fn diff_for_target<'r>(
+    snapshot: &Snapshot<'r>,
+    target: &DiffTarget,
+    opts: &mut git2::DiffOptions,
+) -> Result<Diff<'r>, CoreError> {
+    let repo = snapshot.repo;
+    ensure_target_available(repo, target)?;
+    let diff = match target {
+        DiffTarget::Staged => repo.diff_tree_to_index(snapshot.head_tree(), None, Some(opts))?,
+        DiffTarget::Unstaged => {
+            include_untracked_content(opts);
+            repo.diff_index_to_workdir(None, Some(opts))?
+        }
+        DiffTarget::Uncommitted => {
+            include_untracked_content(opts);
+            repo.diff_tree_to_workdir_with_index(snapshot.head_tree(), Some(opts))?
+        }
+        DiffTarget::Range { from, to } => {
+            let (old_tree, new_tree) = range_trees(repo, from.as_deref(), to)?;
+            repo.diff_tree_to_tree(old_tree.as_ref(), Some(&new_tree), Some(opts))?
+        }
+        DiffTarget::MergeBase { base, head } => {
+            let (old_tree, new_tree) = merge_base_trees(repo, base, head)?;
+            repo.diff_tree_to_tree(old_tree.as_ref(), Some(&new_tree), Some(opts))?
+        }
+        DiffTarget::Stash { index } => {
+            let trees = stash_trees(repo, *index)?;
+            let mut diff =
+                repo.diff_tree_to_tree(Some(&trees.base.tree()?), Some(&trees.stash), Some(opts))?;
+            if let Some(untracked) = &trees.untracked {
+                diff.merge(&repo.diff_tree_to_tree(None, Some(untracked), Some(opts))?)?;
+            }
+            diff
+        }
+    };
+    Ok(diff)
+}
+
+pub(crate) fn find_similar(diff: &mut Diff, options: &DiffOptions) -> Result<(), CoreError> {
+    if options.renames == RenameDetection::Off {
+        return Ok(());
+    }
+
+    let mut find_opts = git2::DiffFindOptions::new();
+    find_opts
+        .renames(true)
+        .for_untracked(true)
+        .rename_threshold(options.similarity_threshold)
+        .copies(options.renames == RenameDetection::RenamesAndCopies)
+        .copy_threshold(options.similarity_threshold);
+    diff.find_similar(Some(&mut find_opts))?;
+    Ok(())
+}
+
+/// The score in a raw diff line such as `:100644 100644 abc def R087\told\tnew`.
+fn raw_score(line: &[u8]) -> Option<u16> {
+    let header = line.split(|b| *b == b'\t').next()?;
+    let status = header.rsplit(|b| *b == b' ').next()?;
+    std::str::from_utf8(status.get(1..)?).ok()?.parse().ok()
+}
+
+/// Rename and copy scores keyed by new path, for a diff that has been through `find_similar`.
+/// git2 does not expose the score it assigned, so it is read back from the raw output.
+pub(crate) fn diff_similarity(diff: &Diff) -> Result<HashMap<String, u16>, CoreError> {
+    let mut scores = HashMap::new();
+    if !diff
+        .deltas()
+        .any(|d| matches!(d.status(), Delta::Renamed | Delta::Copied))
+    {
+        return Ok(scores);
+    }
+
+    diff.print(DiffFormat::Raw, |delta, _, line| {
+        if matches!(delta.status(), Delta::Renamed | Delta::Copied)
+            && let (Some(path), Some(score)) = (delta.new_file().path(), raw_score(line.content()))
+        {
+            scores.insert(path.to_string_lossy().into_owned(), score);
+        }
+        true
+    })?;
+    Ok(scores)
+}
+
+/// Fills in the scores of renamed status entries. `statuses` pairs them without exposing a diff,
+/// so `rediff` diffs only the paired paths and `find_similar` pairs them again.
+fn score_renamed_entries<'r>(
+    entries: &mut [FileEntry],
+    rediff: impl FnOnce(&mut git2::DiffOptions) -> Result<Diff<'r>, CoreError>,
+) -> Result<(), CoreError> {
+    let mut opts = git2::DiffOptions::new();
+    opts.disable_pathspec_match(true);
+    let mut paired = false;
+    for entry in entries.iter() {
+        if let FileEntryKind::Renamed { old_path, .. } = &entry.kind {
+            opts.pathspec(old_path).pathspec(&entry.path);
+            paired = true;
+        }
+    }
+    if !paired {
+        return Ok(());
+    }
+
+    let mut diff = rediff(&mut opts)?;
+    find_similar(&mut diff, &DiffOptions::default())?;
+    let scores = diff_similarity(&diff)?;
+    for entry in entries {
+        if let FileEntryKind::Renamed { similarity, .. } = &mut entry.kind {
+            *similarity = scores.get(&entry.path).copied().unwrap_or(0);
+        }
+    }
+    Ok(())
+}
+
+pub(crate) fn is_gitlink(delta: &git2::DiffDelta) -> bool {
+    delta.new_file().mode() == FileMode::Commit || delta.old_file().mode() == FileMode::Commit
+}
+
+pub(crate) fn nonzero_id(id: Oid) -> Option<String> {
+    (!id.is_zero()).then(|| id.to_string())
+}
+
+/// Describes a gitlink delta; `workdir` adds the submodule's own dirty state.
+fn submodule_kind(
+    repo: &Repository,
+    delta: &git2::DiffDelta,
+    workdir: bool,
+) -> Option<FileEntryKind> {
+    if !is_gitlink(delta) {
+        return None;
+    }
+
+    let (modified_content, untracked_content) = if workdir {
+        let path = delta
+            .new_file()
+            .path()
+            .or_else(|| delta.old_file().path())?;
+        repo.submodule_status(&path.to_string_lossy(), SubmoduleIgnore::None)
+            .map_or((false, false), |status| {
+                (
+                    status.intersects(
+                        SubmoduleStatus::WD_INDEX_MODIFIED | SubmoduleStatus::WD_WD_MODIFIED,
+                    ),
+                    status.contains(SubmoduleStatus::WD_UNTRACKED),
+                )
+            })
+    } else {
+        (false, false)
+    };
+
+    Some(FileEntryKind::Submodule {
+        old_commit: nonzero_id(delta.old_file().id()),
+        new_commit: nonzero_id(delta.new_file().id()),
+        modified_content,
+        untracked_content,
+    })
+}
+
+pub(crate) fn delta_to_entry(
+    repo: &Repository,
+    delta: &git2::DiffDelta,
+    scores: &HashMap<String, u16>,
+) -> Option<FileEntry> {
+    let path_of = |file: git2::DiffFile| file.path().map(|p| p.to_string_lossy().into_owned());
+    let new_path = path_of(delta.new_file());
+    let old_path = path_of(delta.old_file());
+
+    if let Some(kind) = submodule_kind(repo, delta, false) {
+        return Some(FileEntry {
+            path: new_path.or(old_path)?,
+            kind,
+            stats: None,
+        });
+    }
+
+    let similarity = new_path
+        .as_ref()
+        .and_then(|p| scores.get(p))
+        .copied()
+        .unwrap_or(0);
+    let (path, kind) = match delta.status() {
+        Delta::Added => (new_path?, FileEntryKind::Added),
+        Delta::Deleted => (old_path?, FileEntryKind::Deleted),
+        Delta::Modified => (new_path?, FileEntryKind::Modified),
+        Delta::Renamed => (
+            new_path?,
+            FileEntryKind::Renamed {
+                old_path: old_path.unwrap_or_default(),
+                similarity,
+            },
+        ),
+        Delta::Copied => (
+            new_path?,
+            FileEntryKind::Copied {
+                old_path: old_path.unwrap_or_default(),
+                similarity,
+            },
+        ),
+        Delta::Typechange => (new_path?, FileEntryKind::Typechange),
+        Delta::Untracked => (new_path?, FileEntryKind::Untracked),
+        Delta::Conflicted => (new_path.or(old_path)?, FileEntryKind::Conflicted),
+        _ => return None,
+    };
+
+    Some(FileEntry {
+        path,
+        kind,
+        stats: None,
+    })
+}
+
+/// Line counts for every delta in `diff`, keyed by the entry path, from a single pass over it.
+pub(crate) fn diff_stats(diff: &Diff) -> Result<HashMap<String, DiffStats>, CoreError> {
+    let stats: RefCell<HashMap<String, DiffStats>> = RefCell::new(HashMap::new());
+    let delta_path = |delta: &git2::DiffDelta| {
+        delta
+            .new_file()
+            .path()
+            .or_else(|| delta.old_file().path())
+            .map(|p| p.to_string_lossy().into_owned())
+            .unwrap_or_default()
+    };
+
+    diff.foreach(
+        &mut |delta, _| {
+            let mut stats = stats.borrow_mut();
+            let entry = stats.entry(delta_path(&delta)).or_default();
+            entry.is_binary = delta.flags().is_binary();
+            true
+        },
+        Some(&mut |delta, _| {
+            let mut stats = stats.borrow_mut();
+            stats.entry(delta_path(&delta)).or_default().is_binary = true;
+            true
+        }),
+        None,
+        Some(&mut |delta, _, line| {
+            let mut stats = stats.borrow_mut();
+            let entry = stats.entry(delta_path(&delta)).or_default();
+            match line.origin_value() {
+                DiffLineType::Addition => entry.insertions += 1,
+                DiffLineType::Deletion => entry.deletions += 1,
+                _ => {}
+            }
+            true
+        }),
+    )?;
+
+    Ok(stats.into_inner())
+}
+
+fn apply_stats(entries: &mut [FileEntry], stats: &HashMap<String, DiffStats>) {
+    for entry in entries {
+        entry.stats = Some(stats.get(&entry.path).copied().unwrap_or_default());
+    }
+}
+
+/// Line counts for one status side, skipping the diff entirely when the side is empty.
+fn side_stats(
+    repo: &Snapshot,
+    target: &DiffTarget,
+    entries: &[&[FileEntry]],
+) -> Result<HashMap<String, DiffStats>, CoreError> {
+    if entries.iter().all(|e| e.is_empty()) {
+        return Ok(HashMap::new());
+    }
+    let mut diff = diff_for_target(repo, target, &mut git2::DiffOptions::new())?;
+    // Match the rename pairing `statuses` uses so renamed entries find their counts.
+    find_similar(&mut diff, &DiffOptions::default())?;
+    diff_stats(&diff)
+}
+
+/// Fills in line statistics for the staged, unstaged and untracked lists of `status`.
+pub(crate) fn add_status_stats(repo: &Snapshot, status: &mut GitStatus) -> Result<(), CoreError> {
+    let staged = side_stats(repo, &DiffTarget::Staged, &[&status.staged])?;
+    apply_stats(&mut status.staged, &staged);
+    let unstaged = side_stats(
+        repo,
+        &DiffTarget::Unstaged,
+        &[&status.unstaged, &status.untracked],
+    )?;
+    apply_stats(&mut status.unstaged, &unstaged);
+    apply_stats(&mut status.untracked, &unstaged);
+    Ok(())
+}
+
+pub fn get_changed_files(
+    repo_path: &Path,
+    target: &DiffTarget,
+    options: &DiffOptions,
+) -> Result<Vec<FileEntry>, CoreError> {
+    let repo = discover_repository(repo_path)?;
+    get_changed_files_with_repo(&Snapshot::new(&repo), target, options)
+}
+
+pub(crate) fn get_changed_files_with_repo(
+    repo: &Snapshot,
+    target: &DiffTarget,
+    options: &DiffOptions,
+) -> Result<Vec<FileEntry>, CoreError> {
+    ensure_target_available(repo, target)?;
+    match target {
+        DiffTarget::Staged => {
+            let mut entries = get_status_with_repo(repo)?.staged;
+            let line_stats = side_stats(repo, target, &[&entries])?;
+            apply_stats(&mut entries, &line_stats);
+            Ok(entries)
+        }
+        DiffTarget::Unstaged => {
+            let status = get_status_with_repo(repo)?;
+            let mut entries = status.unstaged;
+            entries.extend(status.untracked);
+            let line_stats = side_stats(repo, target, &[&entries])?;
+            apply_stats(&mut entries, &line_stats);
+            Ok(entries)
+        }
+        DiffTarget::Uncommitted
+        | DiffTarget::Range { .. }
+        | DiffTarget::MergeBase { .. }
+        | DiffTarget::Stash { .. } => {
+            let mut opts = git2::DiffOptions::new();
+            let mut diff = diff_for_target(repo, target, &mut opts)?;
+            find_similar(&mut diff, options)?;
+            let scores = diff_similarity(&diff)?;
+            let mut entries: Vec<FileEntry> = diff
+                .deltas()
+                .filter_map(|d| delta_to_entry(repo, &d, &scores))
+                .collect();
+            apply_stats(&mut entries, &diff_stats(&diff)?);
+            Ok(entries)
+        }
+    }
+}
+
+pub(crate) fn head_state(repo: &Repository) -> Result<HeadState, CoreError> {
+    match repo.head() {
+        Ok(head) if head.is_branch() => Ok(HeadState::Branch {
+            name: head.shorthand().unwrap_or_default().to_owned(),
+        }),
+        Ok(head) => Ok(HeadState::Detached {
+            commit_id: head.peel_to_commit()?.id().to_string(),
+        }),
+        Err(e) if e.code() == ErrorCode::UnbornBranch => {
+            let target = repo
+                .find_reference("HEAD")?
+                .symbolic_target()
+                .unwrap_or_default()
+                .to_owned();
+            Ok(HeadState::Unborn {
+                name: target
+                    .strip_prefix("refs/heads/")
+                    .unwrap_or(&target)
+                    .to_owned(),
+            })
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+fn upstream_status(
+    repo: &Repository,
+    head: &HeadState,
+) -> Result<Option<UpstreamStatus>, CoreError> {
+    let HeadState::Branch { name } = head else {
+        return Ok(None);
+    };
+    let branch = repo.find_branch(name, BranchType::Local)?;
+    let Ok(upstream) = branch.upstream() else {
+        return Ok(None);
+    };
+
+    let (Some(local), Some(remote)) = (branch.get().target(), upstream.get().target()) else {
+        return Ok(None);
+    };
+    let (ahead, behind) = repo.graph_ahead_behind(local, remote)?;
+    Ok(Some(UpstreamStatus {
+        name: upstream.name()?.unwrap_or_default().to_owned(),
+        ahead: u32::try_from(ahead).unwrap_or(u32::MAX),
+        behind: u32::try_from(behind).unwrap_or(u32::MAX),
+    }))
+}
+
+/// Reads a rebase progress counter such as `rebase-merge/msgnum`.
+fn rebase_counter(repo: &Repository, file: &str) -> Option<u32> {
+    ["rebase-merge", "rebase-apply"].iter().find_map(|dir| {
+        std::fs::read_to_string(repo.path().join(dir).join(file))
+            .ok()
+            .and_then(|s| s.trim().parse().ok())
+    })
+}
+
+fn repo_operation(repo: &Repository) -> Option<RepoOperation> {
+    match repo.state() {
+        RepositoryState::Clean => None,
+        RepositoryState::Merge => Some(RepoOperation::Merge),
+        RepositoryState::Revert | RepositoryState::RevertSequence => Some(RepoOperation::Revert),
+        RepositoryState::CherryPick | RepositoryState::CherryPickSequence => {
+            Some(RepoOperation::CherryPick)
+        }
+        RepositoryState::Bisect => Some(RepoOperation::Bisect),
+        RepositoryState::ApplyMailbox => Some(RepoOperation::ApplyMailbox),
+        RepositoryState::Rebase
+        | RepositoryState::RebaseInteractive
+        | RepositoryState::RebaseMerge
+        | RepositoryState::ApplyMailboxOrRebase => Some(RepoOperation::Rebase {
+            step: rebase_counter(repo, "msgnum").or_else(|| rebase_counter(repo, "next")),
+            total: rebase_counter(repo, "end").or_else(|| rebase_counter(repo, "last")),
+        }),
+    }
+}
+
 pub fn get_status(repo_path: &Path) -> Result<GitStatus, CoreError> {
     let repo = discover_repository(repo_path)?;
-    get_status_with_repo(&repo)
+    get_status_with_repo(&Snapshot::new(&repo))
+}
+
+/// Like [`get_status`], with per-file line statistics, which cost a diff of each non-empty side.
+pub fn get_status_with_stats(repo_path: &Path) -> Result<GitStatus, CoreError> {
+    let repo = discover_repository(repo_path)?;
+    let snapshot = Snapshot::new(&repo);
+    let mut status = get_status_with_repo(&snapshot)?;
+    add_status_stats(&snapshot, &mut status)?;
+    Ok(status)
+}
+
+/// New and old path of a status entry's rename delta.
+fn renamed_paths(delta: Option<git2::DiffDelta>, default_path: &str) -> (String, Option<String>) {
+    let path_of = |file: git2::DiffFile| file.path().map(|p| p.to_string_lossy().into_owned());
+    let Some(delta) = delta else {
+        return (default_path.to_owned(), None);
+    };
+    (
+        path_of(delta.new_file()).unwrap_or_else(|| default_path.to_owned()),
+        path_of(delta.old_file()),
+    )
+}
+
+/// Folds both sides of a status entry into the single change from HEAD to the working tree.
+fn uncommitted_entry(repo: &Repository, entry: &git2::StatusEntry) -> Option<FileEntry> {
+    let path_of = |file: git2::DiffFile| file.path().map(|p| p.to_string_lossy().into_owned());
+    let status = entry.status();
+    let staged = entry.head_to_index();
+    let unstaged = entry.index_to_workdir();
+
+    let path = unstaged
+        .as_ref()
+        .or(staged.as_ref())
+        .and_then(|d| path_of(d.new_file()))
+        .or_else(|| entry.path().map(str::to_owned))?;
+    let head_path = staged
+        .as_ref()
+        .or(unstaged.as_ref())
+        .and_then(|d| path_of(d.old_file()))
+        .unwrap_or_else(|| path.clone());
+
+    let submodule = unstaged
+        .as_ref()
+        .and_then(|d| submodule_kind(repo, d, true))
+        .or_else(|| staged.as_ref().and_then(|d| submodule_kind(repo, d, false)));
+    if let Some(mut kind) = submodule {
+        if let (FileEntryKind::Submodule { old_commit, .. }, Some(staged)) = (&mut kind, &staged) {
+            *old_commit = nonzero_id(staged.old_file().id());
+        }
+        return Some(FileEntry {
+            path,
+            kind,
+            stats: None,
+        });
+    }
+
+    let (path, kind) = if status.is_conflicted() {
+        (path, FileEntryKind::Conflicted)
+    } else if status.contains(Status::WT_NEW) {
+        let kind = if status.contains(Status::INDEX_DELETED) {
+            FileEntryKind::Modified
+        } else {
+            FileEntryKind::Untracked
+        };
+        (path, kind)
+    } else if status.contains(Status::INDEX_NEW) {
+        if status.contains(Status::WT_DELETED) {
+            return None;
+        }
+        (path, FileEntryKind::Added)
+    } else if status.intersects(Status::INDEX_DELETED | Status::WT_DELETED) {
+        (head_path, FileEntryKind::Deleted)
+    } else if head_path != path {
+        (
+            path,
+            FileEntryKind::Renamed {
+                old_path: head_path,
+                similarity: 0,
+            },
+        )
+    } else if status.intersects(Status::INDEX_TYPECHANGE | Status::WT_TYPECHANGE) {
+        (path, FileEntryKind::Typechange)
+    } else {
+        (path, FileEntryKind::Modified)
+    };
+
+    Some(FileEntry {
+        path,
+        kind,
+        stats: None,
+    })
 }
 
-fn get_status_with_repo(repo: &Repository) -> Result<GitStatus, CoreError> {
+pub(crate) fn status_options() -> StatusOptions {
     let mut opts = StatusOptions::new();
     opts.include_untracked(true)
         .recurse_untracked_dirs(true)
         .include_ignored(false)
         .renames_head_to_index(true)
         .renames_index_to_workdir(true);
+    opts
+}
 
-    let statuses = repo.statuses(Some(&mut opts))?;
+/// Staged, unstaged and untracked entry counts, without building entries or scoring renames.
+pub(crate) fn change_summary(repo: &Repository) -> Result<ChangeSummary, CoreError> {
+    let mut summary = ChangeSummary::default();
+    if repo.is_bare() {
+        return Ok(summary);
+    }
+    for entry in repo.statuses(Some(&mut status_options()))?.iter() {
+        let status = entry.status();
+        if status_to_basic(status, true).is_some() {
+            summary.staged += 1;
+        }
+        match status_to_basic(status, false) {
+            Some(BasicStatus::Untracked) => summary.untracked += 1,
+            Some(_) => summary.unstaged += 1,
+            None => {}
+        }
+    }
+    Ok(summary)
+}
+
+pub(crate) fn get_status_with_repo(repo: &Snapshot) -> Result<GitStatus, CoreError> {
+    let head = head_state(repo)?;
+    if repo.is_bare() {
+        return Ok(GitStatus {
+            bare: true,
+            upstream: upstream_status(repo, &head)?,
+            operation: None,
+            head,
+            staged: Vec::new(),
+            unstaged: Vec::new(),
+            untracked: Vec::new(),
+            uncommitted: Vec::new(),
+        });
+    }
+
+    let statuses = repo.statuses(Some(&mut status_options()))?;
 
     let mut staged = Vec::new();
     let mut unstaged = Vec::new();
     let mut untracked = Vec::new();
+    let mut uncommitted = Vec::new();
 
     for entry in statuses.iter() {
+        uncommitted.extend(uncommitted_entry(repo, &entry));
         let default_path = entry.path().unwrap_or("").to_owned();
         let status = entry.status();
 
         if let Some(basic_status) = status_to_basic(status, true) {
             let (path, old_path) = if status.contains(Status::INDEX_RENAMED) {
-                let diff_delta = entry.head_to_index();
-                let new_path = diff_delta
-                    .as_ref()
-                    .and_then(|d| d.new_file().path())
-                    .map_or_else(
-                        || default_path.clone(),
-                        |p| p.to_string_lossy().into_owned(),
-                    );
-                let old = diff_delta
-                    .and_then(|d| d.old_file().path())
-                    .map(|p| p.to_string_lossy().into_owned());
-                (new_path, old)
+                renamed_paths(entry.head_to_index(), &default_path)
             } else {
                 (default_path.clone(), None)
             };
 
+            let kind = entry
+                .head_to_index()
+                .and_then(|d| submodule_kind(repo, &d, false))
+                .unwrap_or_else(|| basic_to_kind(basic_status, old_path, 0));
             staged.push(FileEntry {
                 path,
-                kind: basic_to_kind(basic_status, old_path),
+                kind,
+                stats: None,
             });
         }
 
@@ -151,291 +797,817 @@ fn get_status_with_repo(repo: &Repository) -> Result<GitStatus, CoreError> {
                 untracked.push(FileEntry {
                     path: default_path.clone(),
                     kind: FileEntryKind::Untracked,
+                    stats: None,
                 });
             } else {
                 let (path, old_path) = if status.contains(Status::WT_RENAMED) {
-                    let diff_delta = entry.index_to_workdir();
-                    let new_path = diff_delta
-                        .as_ref()
-                        .and_then(|d| d.new_file().path())
-                        .map_or_else(
-                            || default_path.clone(),
-                            |p| p.to_string_lossy().into_owned(),
-                        );
-                    let old = diff_delta
-                        .and_then(|d| d.old_file().path())
-                        .map(|p| p.to_string_lossy().into_owned());
-                    (new_path, old)
+                    renamed_paths(entry.index_to_workdir(), &default_path)
                 } else {
                     (default_path, None)
                 };
 
+                let kind = entry
+                    .index_to_workdir()
+                    .and_then(|d| submodule_kind(repo, &d, true))
+                    .unwrap_or_else(|| basic_to_kind(basic_status, old_path, 0));
                 unstaged.push(FileEntry {
                     path,
-                    kind: basic_to_kind(basic_status, old_path),
+                    kind,
+                    stats: None,
                 });
             }
         }
     }
 
+    score_renamed_entries(&mut staged, |opts| {
+        diff_for_target(repo, &DiffTarget::Staged, opts)
+    })?;
+    score_renamed_entries(&mut unstaged, |opts| {
+        diff_for_target(repo, &DiffTarget::Unstaged, opts)
+    })?;
+    score_renamed_entries(&mut uncommitted, |opts| {
+        diff_for_target(repo, &DiffTarget::Uncommitted, opts)
+    })?;
+
     Ok(GitStatus {
+        bare: false,
+        upstream: upstream_status(repo, &head)?,
+        operation: repo_operation(repo),
+        head,
         staged,
         unstaged,
         untracked,
+        uncommitted,
     })
 }
 
 pub fn get_file_diff(
     repo_path: &Path,
     file_path: &str,
-    target: DiffTarget,
+    target: &DiffTarget,
+    options: &DiffOptions,
 ) -> Result<FileDiff, CoreError> {
     let repo = discover_repository(repo_path)?;
-    get_file_diff_with_repo(&repo, file_path, target)
+    get_file_diff_with_repo(&Snapshot::new(&repo), file_path, target, options)
 }
 
-fn get_file_diff_with_repo(
-    repo: &Repository,
-    file_path: &str,
-    target: DiffTarget,
-) -> Result<FileDiff, CoreError> {
-    use std::cell::RefCell;
-
-    let mut opts = DiffOptions::new();
-    opts.pathspec(file_path);
-    opts.context_lines(5);
-
-    let diff = match target {
-        DiffTarget::Staged => match repo.head().ok().and_then(|h| h.peel_to_tree().ok()) {
-            Some(head) => repo.diff_tree_to_index(Some(&head), None, Some(&mut opts))?,
-            None => repo.diff_tree_to_index(None, None, Some(&mut opts))?,
-        },
-        DiffTarget::Unstaged => repo.diff_index_to_workdir(None, Some(&mut opts))?,
-    };
-
-    let hunks: RefCell<Vec<DiffHunk>> = RefCell::new(Vec::new());
-    let is_binary: RefCell<bool> = RefCell::new(false);
-    let actual_path: RefCell<String> = RefCell::new(file_path.to_owned());
-    let old_path: RefCell<Option<String>> = RefCell::new(None);
-
-    diff.print(git2::DiffFormat::Patch, |delta, hunk, line| {
-        if let Some(p) = delta.new_file().path() {
-            *actual_path.borrow_mut() = p.to_string_lossy().into_owned();
-        }
-        if let Some(p) = delta.old_file().path() {
-            let old = p.to_string_lossy().into_owned();
-            let current_path = actual_path.borrow();
-            if old != *current_path {
-                *old_path.borrow_mut() = Some(old);
-            }
-        }
-
-        if delta.flags().is_binary() {
-            *is_binary.borrow_mut() = true;
-        }
-
-        if let Some(h) = hunk {
-            let mut hunks_ref = hunks.borrow_mut();
-            let needs_new_hunk = hunks_ref.last().is_none_or(|last| {
-                last.old_start != h.old_start()
-                    || last.new_start != h.new_start()
-                    || last.old_lines != h.old_lines()
-                    || last.new_lines != h.new_lines()
-            });
-
-            if needs_new_hunk {
-                hunks_ref.push(DiffHunk {
-                    old_start: h.old_start(),
-                    old_lines: h.old_lines(),
-                    new_start: h.new_start(),
-                    new_lines: h.new_lines(),
-                    header: String::from_utf8_lossy(h.header()).trim_end().to_owned(),
-                    lines: Vec::new(),
-                });
-            }
-        }
-
-        let change_type = match line.origin_value() {
-            DiffLineType::Addition => LineChangeType::Addition,
-            DiffLineType::Deletion => LineChangeType::Deletion,
-            DiffLineType::Context => LineChangeType::Context,
-            DiffLineType::ContextEOFNL | DiffLineType::AddEOFNL | DiffLineType::DeleteEOFNL => {
-                LineChangeType::Context
-            }
-            _ => return true,
+pub(crate) fn patch_hunks(patch: &git2::Patch) -> Result<Vec<DiffHunk>, CoreError> {
+    let mut hunks = Vec::with_capacity(patch.num_hunks());
+
+    for hunk_idx in 0..patch.num_hunks() {
+        let (h, line_count) = patch.hunk(hunk_idx)?;
+        let mut hunk = DiffHunk {
+            old_start: h.old_start(),
+            old_lines: h.old_lines(),
+            new_start: h.new_start(),
+            new_lines: h.new_lines(),
+            header: String::from_utf8_lossy(h.header()).trim_end().to_owned(),
+            lines: Vec::with_capacity(line_count),
         };
 
-        let content = String::from_utf8_lossy(line.content())
-            .trim_end_matches('\n')
-            .to_owned();
+        for line_idx in 0..line_count {
+            let line = patch.line_in_hunk(hunk_idx, line_idx)?;
+            let change_type = match line.origin_value() {
+                DiffLineType::Addition => LineChangeType::Addition,
+                DiffLineType::Deletion => LineChangeType::Deletion,
+                DiffLineType::Context => LineChangeType::Context,
+                DiffLineType::ContextEOFNL | DiffLineType::AddEOFNL | DiffLineType::DeleteEOFNL => {
+                    LineChangeType::Context
+                }
+                _ => continue,
+            };
 
-        let mut hunks_ref = hunks.borrow_mut();
-        if let Some(hunk) = hunks_ref.last_mut() {
             hunk.lines.push(DiffLine {
                 change_type,
-                content,
+                content: String::from_utf8_lossy(line.content())
+                    .trim_end_matches('\n')
+                    .to_owned(),
                 old_line_no: line.old_lineno(),
                 new_line_no: line.new_lineno(),
+                inline_changes: Vec::new(),
+                blame: None,
             });
         }
 
-        true
-    })?;
+        inline_diff::annotate_hunk(&mut hunk);
+        hunks.push(hunk);
+    }
 
-    let content = if is_binary.into_inner() {
-        DiffContent::Binary
-    } else {
-        DiffContent::Text {
-            hunks: hunks.into_inner(),
-        }
+    Ok(hunks)
+}
+
+fn delta_file_diff(
+    diff: &Diff,
+    idx: usize,
+    scores: &HashMap<String, u16>,
+) -> Result<FileDiff, CoreError> {
+    let file_patch = git2::Patch::from_diff(diff, idx)?;
+    let delta = file_patch
+        .as_ref()
+        .map_or_else(|| diff.get_delta(idx), |p| Some(p.delta()))
+        .ok_or_else(|| CoreError::InvalidPath(format!("No diff delta at index {idx}")))?;
+
+    let path_of = |file: git2::DiffFile| file.path().map(|p| p.to_string_lossy().into_owned());
+    let new_file_path = path_of(delta.new_file());
+    let old_file_path = path_of(delta.old_file());
+    let path = new_file_path
+        .clone()
+        .or_else(|| old_file_path.clone())
+        .unwrap_or_default();
+    let old_path = old_file_path.filter(|o| new_file_path.as_ref().is_some_and(|n| n != o));
+
+    let similarity = matches!(delta.status(), Delta::Renamed | Delta::Copied)
+        .then(|| scores.get(&path).copied().unwrap_or(0));
+
+    let content = match &file_patch {
+        Some(text_patch) if !delta.flags().is_binary() => DiffContent::Text {
+            hunks: patch_hunks(text_patch)?,
+        },
+        Some(_) => DiffContent::Binary,
+        None if delta.flags().is_binary() => DiffContent::Binary,
+        None => DiffContent::Text { hunks: Vec::new() },
     };
 
     Ok(FileDiff {
-        path: actual_path.into_inner(),
-        old_path: old_path.into_inner(),
+        path,
+        old_path,
+        similarity,
         content,
     })
 }
 
-pub fn get_git_file_contents(
-    repo_path: &Path,
-    file_path: &str,
-    target: DiffTarget,
-) -> Result<GitFileContents, CoreError> {
-    let repo = discover_repository(repo_path)?;
-    get_git_file_contents_with_repo(&repo, file_path, target)
+/// Paths `find_similar` could pair with a file of `status`: deletions for an added or untracked
+/// file, additions for a deleted one, and modified files as copy sources.
+fn similar_candidates(
+    repo: &Snapshot,
+    target: &DiffTarget,
+    status: Delta,
+    options: &DiffOptions,
+) -> Result<Vec<PathBuf>, CoreError> {
+    let copies = options.renames == RenameDetection::RenamesAndCopies;
+    let listing = diff_for_target(repo, target, &mut git2::DiffOptions::new())?;
+    let candidates = listing
+        .deltas()
+        .filter(|d| match (status, d.status()) {
+            (Delta::Added | Delta::Untracked, Delta::Deleted)
+            | (Delta::Deleted, Delta::Added | Delta::Untracked) => true,
+            (Delta::Added | Delta::Untracked, Delta::Modified) => copies,
+            _ => false,
+        })
+        .filter_map(|d| d.new_file().path().or_else(|| d.old_file().path()))
+        .map(Path::to_path_buf)
+        .collect();
+    Ok(candidates)
 }
 
-fn get_git_file_contents_with_repo(
-    repo: &Repository,
+/// The rename or copy involving `file_path`, found by running `find_similar` over only the
+/// paths it could pair with.
+fn similar_delta<'r>(
+    repo: &Snapshot<'r>,
     file_path: &str,
-    target: DiffTarget,
-) -> Result<GitFileContents, CoreError> {
-    let file_path_obj = Path::new(file_path);
-    if file_path_obj.is_absolute() {
-        return Err(CoreError::InvalidPath(
-            "Absolute paths are not allowed".to_owned(),
-        ));
+    target: &DiffTarget,
+    status: Delta,
+    options: &DiffOptions,
+) -> Result<Option<(Diff<'r>, usize)>, CoreError> {
+    if options.renames == RenameDetection::Off {
+        return Ok(None);
     }
-
-    if target == DiffTarget::Unstaged {
-        let workdir = repo
-            .workdir()
-            .ok_or_else(|| CoreError::InvalidPath("Repository has no working directory".into()))?;
-        let full_path = workdir.join(file_path);
-        let canonical_workdir = workdir
-            .canonicalize()
-            .map_err(|e| CoreError::io(workdir, e))?;
-        let canonical_full = if full_path.exists() {
-            full_path.canonicalize()
-        } else {
-            full_path
-                .parent()
-                .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::NotFound, "No parent"))
-                .and_then(std::path::Path::canonicalize)
-                .map(|p| p.join(full_path.file_name().unwrap_or_default()))
-        }
-        .map_err(|e| CoreError::io(&full_path, e))?;
-
-        if !canonical_full.starts_with(&canonical_workdir) {
-            return Err(CoreError::InvalidPath(
-                "Path traversal detected: path escapes repository".into(),
-            ));
-        }
+    let candidates = similar_candidates(repo, target, status, options)?;
+    if candidates.is_empty() {
+        return Ok(None);
     }
 
-    let lang = extension_to_lang(file_path);
+    let mut opts = git2::DiffOptions::new();
+    apply_diff_options(&mut opts, options);
+    opts.pathspec(file_path).disable_pathspec_match(true);
+    for candidate in &candidates {
+        opts.pathspec(candidate);
+    }
+    let mut paired = diff_for_target(repo, target, &mut opts)?;
+    find_similar(&mut paired, options)?;
 
-    let (old_content, new_content) = match target {
-        DiffTarget::Staged => {
-            let head_blob = repo
-                .head()
-                .ok()
-                .and_then(|h| h.peel_to_tree().ok())
-                .and_then(|tree| tree.get_path(Path::new(file_path)).ok())
-                .and_then(|entry| entry.to_object(repo).ok())
-                .and_then(|obj| obj.into_blob().ok());
+    let idx = paired.deltas().position(|d| {
+        matches!(d.status(), Delta::Renamed | Delta::Copied) && delta_touches_path(&d, file_path)
+    });
+    Ok(idx.map(|idx| (paired, idx)))
+}
 
-            let index = repo.index()?;
-            let index_blob = index
-                .get_path(Path::new(file_path), 0)
-                .and_then(|entry| repo.find_blob(entry.id).ok());
+/// The rename or copy a file on the new side of a diff was paired with.
+pub(crate) struct SimilarSource {
+    pub(crate) old_path: String,
+    pub(crate) status: Delta,
+    pub(crate) similarity: u16,
+}
 
-            (blob_to_content(head_blob), blob_to_content(index_blob))
-        }
-        DiffTarget::Unstaged => {
-            let index = repo.index()?;
-            let index_blob = index
-                .get_path(Path::new(file_path), 0)
-                .and_then(|entry| repo.find_blob(entry.id).ok());
-
-            let workdir = repo.workdir().ok_or_else(|| {
-                CoreError::InvalidPath("Repository has no working directory".into())
-            })?;
-            let full_path = workdir.join(file_path);
-            let workdir_content = match std::fs::read(&full_path) {
-                Ok(bytes) => Some(bytes_to_content(&bytes)),
-                Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
-                Err(e) => {
-                    return Err(CoreError::io(&full_path, e));
+/// The rename or copy that produced `file_path` on the new side of `target`, if any.
+pub(crate) fn similar_source(
+    repo: &Snapshot,
+    file_path: &str,
+    target: &DiffTarget,
+    options: &DiffOptions,
+) -> Result<Option<SimilarSource>, CoreError> {
+    let mut opts = git2::DiffOptions::new();
+    opts.pathspec(file_path);
+    let diff = diff_for_target(repo, target, &mut opts)?;
+    let status = diff.deltas().next().map(|d| d.status());
+    if let Some(status @ (Delta::Added | Delta::Untracked)) = status
+        && let Some((paired, idx)) = similar_delta(repo, file_path, target, status, options)?
+        && let Some(delta) = paired.get_delta(idx)
+        && delta.new_file().path() == Some(Path::new(file_path))
+        && let Some(old_path) = delta.old_file().path()
+    {
+        let similarity = diff_similarity(&paired)?.get(file_path).copied();
+        return Ok(Some(SimilarSource {
+            old_path: old_path.to_string_lossy().into_owned(),
+            status: delta.status(),
+            similarity: similarity.unwrap_or_default(),
+        }));
+    }
+    Ok(None)
+}
+
+/// The path `file_path` had on the old side of `target`, following a detected rename or copy.
+fn old_side_path(
+    repo: &Snapshot,
+    file_path: &str,
+    target: &DiffTarget,
+    options: &DiffOptions,
+) -> Result<String, CoreError> {
+    Ok(similar_source(repo, file_path, target, options)?
+        .map_or_else(|| file_path.to_owned(), |source| source.old_path))
+}
+
+fn delta_touches_path(delta: &git2::DiffDelta, file_path: &str) -> bool {
+    let matches = |file: git2::DiffFile| file.path().is_some_and(|p| p == Path::new(file_path));
+    matches(delta.new_file()) || matches(delta.old_file())
+}
+
+pub(crate) fn get_file_diff_with_repo(
+    repo: &Snapshot,
+    file_path: &str,
+    target: &DiffTarget,
+    options: &DiffOptions,
+) -> Result<FileDiff, CoreError> {
+    let mut file_diff = target_file_diff(repo, file_path, target, options)?;
+    if options.blame_deletions {
+        attach_deletion_blame(repo, target, &mut file_diff)?;
+    }
+    Ok(file_diff)
+}
+
+fn target_file_diff(
+    repo: &Snapshot,
+    file_path: &str,
+    target: &DiffTarget,
+    options: &DiffOptions,
+) -> Result<FileDiff, CoreError> {
+    let mut opts = git2::DiffOptions::new();
+    opts.pathspec(file_path);
+    apply_diff_options(&mut opts, options);
+
+    let diff = diff_for_target(repo, target, &mut opts)?;
+
+    let status = diff.deltas().next().map(|d| d.status());
+    if let Some(status @ (Delta::Added | Delta::Deleted | Delta::Untracked)) = status
+        && let Some((paired, idx)) = similar_delta(repo, file_path, target, status, options)?
+    {
+        let scores = diff_similarity(&paired)?;
+        return delta_file_diff(&paired, idx, &scores);
+    }
+
+    if diff.deltas().len() == 0 {
+        return Ok(FileDiff {
+            path: file_path.to_owned(),
+            old_path: None,
+            similarity: None,
+            content: DiffContent::Text { hunks: Vec::new() },
+        });
+    }
+
+    delta_file_diff(&diff, 0, &HashMap::new())
+}
+
+/// Every file diff for `target` from a single diff, returning at most `max_lines` hunk lines.
+/// A file keeps its hunks up to the first one that no longer fits and is flagged as truncated;
+/// what is left of the budget still goes to later files whose hunks fit.
+pub fn get_changeset_diff(
+    repo_path: &Path,
+    target: &DiffTarget,
+    options: &DiffOptions,
+    max_lines: Option<u32>,
+) -> Result<ChangesetDiff, CoreError> {
+    let repo = discover_repository(repo_path)?;
+    get_changeset_diff_with_repo(&Snapshot::new(&repo), target, options, max_lines)
+}
+
+pub(crate) fn get_changeset_diff_with_repo(
+    repo: &Snapshot,
+    target: &DiffTarget,
+    options: &DiffOptions,
+    max_lines: Option<u32>,
+) -> Result<ChangesetDiff, CoreError> {
+    let mut opts = git2::DiffOptions::new();
+    apply_diff_options(&mut opts, options);
+    let mut diff = diff_for_target(repo, target, &mut opts)?;
+    find_similar(&mut diff, options)?;
+    let scores = diff_similarity(&diff)?;
+
+    let mut remaining = max_lines.map_or(usize::MAX, |n| n as usize);
+    let mut files = Vec::with_capacity(diff.deltas().len());
+    for idx in 0..diff.deltas().len() {
+        let mut file_diff = delta_file_diff(&diff, idx, &scores)?;
+        let mut truncated = false;
+        if let DiffContent::Text { hunks } = &mut file_diff.content {
+            let mut kept = 0;
+            for hunk in hunks.iter() {
+                if hunk.lines.len() > remaining {
+                    break;
                 }
-            };
+                remaining -= hunk.lines.len();
+                kept += 1;
+            }
+            if kept < hunks.len() {
+                truncated = true;
+                hunks.truncate(kept);
+            }
+        }
+        if options.blame_deletions {
+            attach_deletion_blame(repo, target, &mut file_diff)?;
+        }
+        files.push(ChangesetFile {
+            diff: file_diff,
+            truncated,
+        });
+    }
+
+    Ok(ChangesetDiff {
+        truncated: files.iter().any(|f| f.truncated),
+        files,
+    })
+}
+
+pub(crate) fn read_workdir_bytes(
+    repo: &Repository,
+    file_path: &str,
+) -> Result<Option<Vec<u8>>, CoreError> {
+    let full_path = require_workdir(repo)?.join(file_path);
+    match std::fs::read(&full_path) {
+        Ok(bytes) => Ok(Some(bytes)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(CoreError::io(&full_path, e)),
+    }
+}
+
+/// Canonicalizes a path that doesn't exist through its nearest existing ancestor, so paths
+/// under directories that are yet to be created are checked too.
+fn canonicalize_missing(path: &Path) -> std::io::Result<PathBuf> {
+    let no_parent = || std::io::Error::new(std::io::ErrorKind::NotFound, "No parent");
+    let mut missing = Vec::new();
+    let mut ancestor = path;
+    while !ancestor.exists() {
+        missing.push(ancestor.file_name().ok_or_else(no_parent)?);
+        ancestor = ancestor.parent().ok_or_else(no_parent)?;
+    }
+    let mut canonical = ancestor.canonicalize()?;
+    canonical.extend(missing.iter().rev());
+    Ok(canonical)
+}
+
+pub(crate) fn validate_target_path(
+    repo: &Repository,
+    file_path: &str,
+    target: &DiffTarget,
+) -> Result<(), CoreError> {
+    let file_path_obj = Path::new(file_path);
+    if file_path_obj.is_absolute() {
+        return Err(CoreError::InvalidPath(
+            "Absolute paths are not allowed".to_owned(),
+        ));
+    }
+
+    if matches!(target, DiffTarget::Unstaged | DiffTarget::Uncommitted) {
+        let workdir = require_workdir(repo)?;
+        let full_path = workdir.join(file_path);
+        let canonical_workdir = workdir
+            .canonicalize()
+            .map_err(|e| CoreError::io(workdir, e))?;
+        let canonical_full = if full_path.exists() {
+            full_path.canonicalize()
+        } else {
+            canonicalize_missing(&full_path)
+        }
+        .map_err(|e| CoreError::io(&full_path, e))?;
+
+        if !canonical_full.starts_with(&canonical_workdir) {
+            return Err(CoreError::InvalidPath(
+                "Path traversal detected: path escapes repository".into(),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+pub(crate) fn file_versions(
+    repo: &Snapshot,
+    file_path: &str,
+    target: &DiffTarget,
+) -> Result<(Option<Arc<[u8]>>, Option<Arc<[u8]>>), CoreError> {
+    cached_file_versions(
+        repo,
+        &mut BlobCache::disabled(),
+        file_path,
+        file_path,
+        target,
+    )
+}
 
-            (blob_to_content(index_blob), workdir_content)
+/// Old and new contents for `target`, reading the old side at `old_path` so renames line up.
+pub(crate) fn cached_file_versions(
+    repo: &Snapshot,
+    blobs: &mut BlobCache,
+    old_path: &str,
+    new_path: &str,
+    target: &DiffTarget,
+) -> Result<(Option<Arc<[u8]>>, Option<Arc<[u8]>>), CoreError> {
+    ensure_target_available(repo, target)?;
+    let index_id = |path: &str| -> Result<Option<Oid>, CoreError> {
+        let index = repo.index()?;
+        Ok(index
+            .get_path(Path::new(path), 0)
+            .filter(|entry| entry.mode != u32::from(FileMode::Commit))
+            .map(|entry| entry.id))
+    };
+    let head_id = |path: &str| tree_blob_id(repo.head_tree(), path);
+
+    let (old_id, new_id) = match target {
+        DiffTarget::Staged => (head_id(old_path), index_id(new_path)?),
+        DiffTarget::Unstaged => {
+            let old_id = index_id(old_path)?;
+            return Ok((
+                old_id.map(|id| blobs.get(repo, id)).transpose()?,
+                read_workdir_bytes(repo, new_path)?.map(Arc::from),
+            ));
+        }
+        DiffTarget::Uncommitted => {
+            let old_id = head_id(old_path);
+            return Ok((
+                old_id.map(|id| blobs.get(repo, id)).transpose()?,
+                read_workdir_bytes(repo, new_path)?.map(Arc::from),
+            ));
+        }
+        DiffTarget::Range { from, to } => {
+            let (old_tree, new_tree) = range_trees(repo, from.as_deref(), to)?;
+            (
+                tree_blob_id(old_tree.as_ref(), old_path),
+                tree_blob_id(Some(&new_tree), new_path),
+            )
+        }
+        DiffTarget::MergeBase { base, head } => {
+            let (old_tree, new_tree) = merge_base_trees(repo, base, head)?;
+            (
+                tree_blob_id(old_tree.as_ref(), old_path),
+                tree_blob_id(Some(&new_tree), new_path),
+            )
+        }
+        DiffTarget::Stash { index } => {
+            let trees = stash_trees(repo, *index)?;
+            (
+                tree_blob_id(Some(&trees.base.tree()?), old_path),
+                tree_blob_id(Some(&trees.stash), new_path)
+                    .or_else(|| tree_blob_id(trees.untracked.as_ref(), new_path)),
+            )
         }
     };
+    Ok((
+        old_id.map(|id| blobs.get(repo, id)).transpose()?,
+        new_id.map(|id| blobs.get(repo, id)).transpose()?,
+    ))
+}
+
+pub fn get_git_file_contents(
+    repo_path: &Path,
+    file_path: &str,
+    target: &DiffTarget,
+) -> Result<GitFileContents, CoreError> {
+    let repo = discover_repository(repo_path)?;
+    get_git_file_contents_with_repo(
+        &Snapshot::new(&repo),
+        &mut BlobCache::disabled(),
+        file_path,
+        target,
+    )
+}
+
+pub(crate) fn get_git_file_contents_with_repo(
+    repo: &Snapshot,
+    blobs: &mut BlobCache,
+    file_path: &str,
+    target: &DiffTarget,
+) -> Result<GitFileContents, CoreError> {
+    validate_target_path(repo, file_path, target)?;
+
+    let old_path = old_side_path(repo, file_path, target, &DiffOptions::default())?;
+    let (old_bytes, new_bytes) = cached_file_versions(repo, blobs, &old_path, file_path, target)?;
 
     Ok(GitFileContents {
         old_file: DiffFile {
-            name: file_path.to_owned(),
-            lang: lang.clone(),
-            content: old_content,
+            lang: extension_to_lang(&old_path),
+            name: old_path,
+            content: old_bytes.as_deref().map(bytes_to_content),
         },
         new_file: DiffFile {
             name: file_path.to_owned(),
-            lang,
-            content: new_content,
+            lang: extension_to_lang(file_path),
+            content: new_bytes.as_deref().map(bytes_to_content),
         },
     })
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use git2::Signature;
-    use std::env;
-    use std::fs;
-    use tempfile::TempDir;
+fn line_counterpart(hunks: &[(u32, u32, u32, u32)], side: DiffSide, line_no: u32) -> Option<u32> {
+    let mut offset: i64 = 0;
+    for &(old_start, old_lines, new_start, new_lines) in hunks {
+        let (start, len) = match side {
+            DiffSide::Old => (old_start, old_lines),
+            DiffSide::New => (new_start, new_lines),
+        };
+        let last = if len == 0 { start } else { start + len - 1 };
+        if len > 0 && (start..=last).contains(&line_no) {
+            return None;
+        }
+        if line_no <= last {
+            break;
+        }
+        offset += match side {
+            DiffSide::Old => i64::from(new_lines) - i64::from(old_lines),
+            DiffSide::New => i64::from(old_lines) - i64::from(new_lines),
+        };
+    }
+    u32::try_from(i64::from(line_no) + offset).ok()
+}
 
-    fn create_test_repo() -> (TempDir, Repository) {
-        let temp_dir = TempDir::new().unwrap();
-        let repo = Repository::init(temp_dir.path()).unwrap();
+pub fn get_context_lines(
+    repo_path: &Path,
+    file_path: &str,
+    target: &DiffTarget,
+    side: DiffSide,
+    start: u32,
+    end: u32,
+    options: &DiffOptions,
+) -> Result<Vec<DiffLine>, CoreError> {
+    let repo = discover_repository(repo_path)?;
+    get_context_lines_with_repo(
+        &Snapshot::new(&repo),
+        &mut BlobCache::disabled(),
+        file_path,
+        target,
+        side,
+        start,
+        end,
+        options,
+    )
+}
+
+pub(crate) fn get_context_lines_with_repo(
+    repo: &Snapshot,
+    blobs: &mut BlobCache,
+    file_path: &str,
+    target: &DiffTarget,
+    side: DiffSide,
+    start: u32,
+    end: u32,
+    options: &DiffOptions,
+) -> Result<Vec<DiffLine>, CoreError> {
+    validate_target_path(repo, file_path, target)?;
+
+    let old_path = old_side_path(repo, file_path, target, options)?;
+    let (old_bytes, new_bytes) = cached_file_versions(repo, blobs, &old_path, file_path, target)?;
+    let old_bytes = old_bytes.unwrap_or_default();
+    let new_bytes = new_bytes.unwrap_or_default();
+
+    let mut opts = git2::DiffOptions::new();
+    apply_diff_options(&mut opts, options);
+    opts.context_lines(0).interhunk_lines(0);
+    let patch = git2::Patch::from_buffers(&old_bytes, None, &new_bytes, None, Some(&mut opts))?;
+    let hunks = (0..patch.num_hunks())
+        .map(|idx| {
+            patch
+                .hunk(idx)
+                .map(|(h, _)| (h.old_start(), h.old_lines(), h.new_start(), h.new_lines()))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let source = match side {
+        DiffSide::Old => &old_bytes,
+        DiffSide::New => &new_bytes,
+    };
+    if source.contains(&0) {
+        return Ok(Vec::new());
+    }
+
+    let first = start.max(1);
+    let lines = String::from_utf8_lossy(source)
+        .split_inclusive('\n')
+        .zip(1u32..)
+        .skip_while(|(_, line_no)| *line_no < first)
+        .take_while(|(_, line_no)| *line_no <= end)
+        .map(|(content, line_no)| {
+            let counterpart = line_counterpart(&hunks, side, line_no);
+            let (change_type, old_line_no, new_line_no) = match (side, counterpart) {
+                (DiffSide::Old, Some(other)) => {
+                    (LineChangeType::Context, Some(line_no), Some(other))
+                }
+                (DiffSide::New, Some(other)) => {
+                    (LineChangeType::Context, Some(other), Some(line_no))
+                }
+                (DiffSide::Old, None) => (LineChangeType::Deletion, Some(line_no), None),
+                (DiffSide::New, None) => (LineChangeType::Addition, None, Some(line_no)),
+            };
+            DiffLine {
+                change_type,
+                content: content.trim_end_matches('\n').to_owned(),
+                old_line_no,
+                new_line_no,
+                inline_changes: Vec::new(),
+                blame: None,
+            }
+        })
+        .collect();
+
+    Ok(lines)
+}
 
-        let mut config = repo.config().unwrap();
-        config.set_str("user.name", "Test User").unwrap();
-        config.set_str("user.email", "test@example.com").unwrap();
+pub(crate) fn head_commit(repo: &Repository) -> Option<Commit<'_>> {
+    repo.head().ok().and_then(|h| h.peel_to_commit().ok())
+}
 
-        (temp_dir, repo)
+fn commit_blame(
+    repo: &Repository,
+    id: Oid,
+    cache: &mut HashMap<Oid, LineBlame>,
+) -> Result<LineBlame, CoreError> {
+    if id.is_zero() {
+        return Ok(LineBlame::Uncommitted);
     }
+    if let Some(blame) = cache.get(&id) {
+        return Ok(blame.clone());
+    }
+    let commit = repo.find_commit(id)?;
+    let blame = LineBlame::Committed {
+        commit_id: id.to_string(),
+        summary: String::from_utf8_lossy(commit.summary_bytes().unwrap_or_default()).into_owned(),
+        author: signature_info(&commit.author()),
+    };
+    cache.insert(id, blame.clone());
+    Ok(blame)
+}
 
-    fn commit_file(repo: &Repository, path: &str, content: &str, message: &str) {
-        let repo_path = repo.workdir().unwrap();
-        fs::write(repo_path.join(path), content).unwrap();
+/// Blames `file_path` as of `newest`; with a `buffer`, lines that differ from it are uncommitted.
+fn blame_lines(
+    repo: &Repository,
+    file_path: &str,
+    newest: Option<Oid>,
+    buffer: Option<&[u8]>,
+) -> Result<Vec<LineBlame>, CoreError> {
+    let all_uncommitted = |buffer: &[u8]| {
+        vec![LineBlame::Uncommitted; buffer.split_inclusive(|&b| b == b'\n').count()]
+    };
 
-        let mut index = repo.index().unwrap();
-        index.add_path(Path::new(path)).unwrap();
-        index.write().unwrap();
+    let Some(newest) = newest else {
+        return Ok(buffer.map(all_uncommitted).unwrap_or_default());
+    };
 
-        let tree_id = index.write_tree().unwrap();
-        let tree = repo.find_tree(tree_id).unwrap();
-        let sig = Signature::now("Test User", "test@example.com").unwrap();
+    let mut opts = BlameOptions::new();
+    opts.newest_commit(newest);
+    let blame = match repo.blame_file(Path::new(file_path), Some(&mut opts)) {
+        Ok(blame) => blame,
+        Err(e) if e.code() == ErrorCode::NotFound => {
+            return match buffer {
+                Some(buffer) => Ok(all_uncommitted(buffer)),
+                None => Err(e.into()),
+            };
+        }
+        Err(e) => return Err(e.into()),
+    };
+    let blame = match buffer {
+        Some(buffer) => blame.blame_buffer(buffer)?,
+        None => blame,
+    };
 
-        let parent = repo.head().ok().and_then(|h| h.peel_to_commit().ok());
-        let parents: Vec<&git2::Commit> = parent.iter().collect();
+    let mut cache = HashMap::new();
+    let mut lines = Vec::new();
+    for hunk in blame.iter() {
+        let line_blame = commit_blame(repo, hunk.final_commit_id(), &mut cache)?;
+        lines.extend(std::iter::repeat_n(line_blame, hunk.lines_in_hunk()));
+    }
+    Ok(lines)
+}
 
-        repo.commit(Some("HEAD"), &sig, &sig, message, &tree, &parents)
-            .unwrap();
+/// Per-line attribution of `file_path` at `rev`, or of the working-tree file when `rev` is `None`.
+/// The result is indexed by line number minus one.
+pub fn blame_file(
+    repo_path: &Path,
+    file_path: &str,
+    rev: Option<&str>,
+) -> Result<Vec<LineBlame>, CoreError> {
+    let repo = discover_repository(repo_path)?;
+    blame_file_with_repo(&repo, file_path, rev)
+}
+
+pub(crate) fn blame_file_with_repo(
+    repo: &Repository,
+    file_path: &str,
+    rev: Option<&str>,
+) -> Result<Vec<LineBlame>, CoreError> {
+    if let Some(rev) = rev {
+        let commit = repo.revparse_single(rev)?.peel_to_commit()?;
+        return blame_lines(repo, file_path, Some(commit.id()), None);
+    }
+
+    validate_target_path(repo, file_path, &DiffTarget::Unstaged)?;
+    let contents = read_workdir_bytes(repo, file_path)?.ok_or_else(|| {
+        CoreError::InvalidPath(format!("File not found in working tree: {file_path}"))
+    })?;
+    let head = head_commit(repo).map(|c| c.id());
+    blame_lines(repo, file_path, head, Some(&contents))
+}
+
+fn old_side_blame(
+    repo: &Snapshot,
+    file_path: &str,
+    target: &DiffTarget,
+) -> Result<Vec<LineBlame>, CoreError> {
+    let head = head_commit(repo).map(|c| c.id());
+    match target {
+        DiffTarget::Staged | DiffTarget::Uncommitted => blame_lines(repo, file_path, head, None),
+        DiffTarget::Unstaged => {
+            let (index_bytes, _) = file_versions(repo, file_path, target)?;
+            blame_lines(
+                repo,
+                file_path,
+                head,
+                Some(&index_bytes.unwrap_or_default()),
+            )
+        }
+        DiffTarget::Range { from, to } => {
+            let old_commit = match from {
+                Some(spec) => Some(repo.revparse_single(spec)?.peel_to_commit()?),
+                None => repo.revparse_single(to)?.peel_to_commit()?.parents().next(),
+            };
+            blame_lines(repo, file_path, old_commit.map(|c| c.id()), None)
+        }
+        DiffTarget::MergeBase { base, head } => {
+            let base_commit = repo.revparse_single(base)?.peel_to_commit()?;
+            let head_commit = repo.revparse_single(head)?.peel_to_commit()?;
+            let merge_base = repo.merge_base(base_commit.id(), head_commit.id())?;
+            blame_lines(repo, file_path, Some(merge_base), None)
+        }
+        DiffTarget::Stash { index } => {
+            let base = stash_trees(repo, *index)?.base.id();
+            blame_lines(repo, file_path, Some(base), None)
+        }
+    }
+}
+
+fn attach_deletion_blame(
+    repo: &Snapshot,
+    target: &DiffTarget,
+    file_diff: &mut FileDiff,
+) -> Result<(), CoreError> {
+    let DiffContent::Text { hunks } = &mut file_diff.content else {
+        return Ok(());
+    };
+    let has_deletions = hunks
+        .iter()
+        .flat_map(|h| &h.lines)
+        .any(|l| l.change_type == LineChangeType::Deletion);
+    if !has_deletions {
+        return Ok(());
+    }
+
+    let old_path = file_diff.old_path.as_deref().unwrap_or(&file_diff.path);
+    let blame = old_side_blame(repo, old_path, target)?;
+    for line in hunks.iter_mut().flat_map(|h| &mut h.lines) {
+        if line.change_type != LineChangeType::Deletion {
+            continue;
+        }
+        line.blame = line
+            .old_line_no
+            .and_then(|n| blame.get(n.checked_sub(1)? as usize))
+            .cloned();
     }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{commit_file, commit_index, create_test_repo};
+    use crate::types::InlineChange;
+    use git2::Signature;
+    use std::env;
+    use std::fs;
+    use tempfile::TempDir;
 
     #[test]
     fn test_open_repository_current_dir() {
@@ -476,6 +1648,115 @@ mod tests {
         assert!(status.staged.is_empty());
         assert!(status.unstaged.is_empty());
         assert!(status.untracked.is_empty());
+        assert!(matches!(status.head, HeadState::Unborn { .. }));
+        assert_eq!(status.upstream, None);
+        assert_eq!(status.operation, None);
+    }
+
+    #[test]
+    fn test_get_status_reports_branch_and_upstream() {
+        let (temp_dir, repo) = create_test_repo();
+
+        commit_file(&repo, "file.txt", "one\n", "Initial commit");
+        let base = repo.head().unwrap().peel_to_commit().unwrap();
+        repo.branch("base", &base, false).unwrap();
+        commit_file(&repo, "file.txt", "two\n", "Second");
+
+        let head_name = repo.head().unwrap().shorthand().unwrap().to_owned();
+        repo.find_branch(&head_name, BranchType::Local)
+            .unwrap()
+            .set_upstream(Some("base"))
+            .unwrap();
+
+        let status = get_status(temp_dir.path()).unwrap();
+        assert_eq!(status.head, HeadState::Branch { name: head_name });
+        assert_eq!(
+            status.upstream,
+            Some(UpstreamStatus {
+                name: "base".to_string(),
+                ahead: 1,
+                behind: 0,
+            })
+        );
+
+        repo.set_head_detached(base.id()).unwrap();
+        let status = get_status(temp_dir.path()).unwrap();
+        assert_eq!(
+            status.head,
+            HeadState::Detached {
+                commit_id: base.id().to_string()
+            }
+        );
+        assert_eq!(status.upstream, None);
+    }
+
+    #[test]
+    fn test_get_status_reports_operation_in_progress() {
+        let (temp_dir, repo) = create_test_repo();
+
+        commit_file(&repo, "file.txt", "one\n", "Initial commit");
+        let head = repo.head().unwrap().target().unwrap();
+
+        fs::write(repo.path().join("MERGE_HEAD"), format!("{head}\n")).unwrap();
+        let status = get_status(temp_dir.path()).unwrap();
+        assert_eq!(status.operation, Some(RepoOperation::Merge));
+        fs::remove_file(repo.path().join("MERGE_HEAD")).unwrap();
+
+        let rebase_dir = repo.path().join("rebase-merge");
+        fs::create_dir(&rebase_dir).unwrap();
+        fs::write(rebase_dir.join("interactive"), "").unwrap();
+        fs::write(rebase_dir.join("msgnum"), "2\n").unwrap();
+        fs::write(rebase_dir.join("end"), "5\n").unwrap();
+        let status = get_status(temp_dir.path()).unwrap();
+        assert_eq!(
+            status.operation,
+            Some(RepoOperation::Rebase {
+                step: Some(2),
+                total: Some(5),
+            })
+        );
+    }
+
+    #[test]
+    fn test_bare_repository_compares_refs_only() {
+        let (temp_dir, repo) = create_test_repo();
+        commit_file(&repo, "file.txt", "one\n", "Initial commit");
+        commit_file(&repo, "file.txt", "two\n", "Second commit");
+
+        let bare_path = temp_dir.path().join("bare.git");
+        git2::build::RepoBuilder::new()
+            .bare(true)
+            .clone(temp_dir.path().to_str().unwrap(), &bare_path)
+            .unwrap();
+
+        let status = get_status(&bare_path).unwrap();
+        assert!(status.bare);
+        assert!(matches!(status.head, HeadState::Branch { .. }));
+        assert!(status.staged.is_empty() && status.unstaged.is_empty());
+
+        let range = DiffTarget::from_revspec("HEAD~1..HEAD");
+        let files = get_changed_files(&bare_path, &range, &DiffOptions::default()).unwrap();
+        assert_eq!(files.len(), 1);
+        let contents = get_git_file_contents(&bare_path, "file.txt", &range).unwrap();
+        assert_eq!(
+            contents.new_file.content,
+            Some(FileContent::Text {
+                contents: "two\n".to_owned(),
+            })
+        );
+
+        for target in [
+            DiffTarget::Staged,
+            DiffTarget::Unstaged,
+            DiffTarget::Uncommitted,
+        ] {
+            let result = get_file_diff(&bare_path, "file.txt", &target, &DiffOptions::default());
+            assert!(matches!(result, Err(CoreError::BareRepository)));
+        }
+        assert!(matches!(
+            blame_file(&bare_path, "file.txt", None),
+            Err(CoreError::BareRepository)
+        ));
     }
 
     #[test]
@@ -544,39 +1825,174 @@ mod tests {
     }
 
     #[test]
-    fn test_get_file_diff_staged_new_file() {
+    fn test_get_status_merges_uncommitted_changes() {
         let (temp_dir, repo) = create_test_repo();
-
-        commit_file(&repo, "existing.txt", "existing", "Initial commit");
-
-        fs::write(temp_dir.path().join("new.txt"), "line 1\nline 2\nline 3\n").unwrap();
+        let root = temp_dir.path();
+        commit_file(&repo, "edited.txt", "one\n", "Initial commit");
+        commit_file(&repo, "removed.txt", "gone\n", "Add removed");
+        commit_file(&repo, "moved.txt", "a\nb\nc\nd\n", "Add moved");
+
+        fs::write(root.join("added.txt"), "new\n").unwrap();
+        fs::write(root.join("scratch.txt"), "tmp\n").unwrap();
+        fs::write(root.join("edited.txt"), "two\n").unwrap();
+        fs::rename(root.join("moved.txt"), root.join("renamed.txt")).unwrap();
         let mut index = repo.index().unwrap();
-        index.add_path(Path::new("new.txt")).unwrap();
+        for path in ["added.txt", "scratch.txt", "edited.txt", "renamed.txt"] {
+            index.add_path(Path::new(path)).unwrap();
+        }
+        index.remove_path(Path::new("moved.txt")).unwrap();
         index.write().unwrap();
 
-        let diff = get_file_diff(temp_dir.path(), "new.txt", DiffTarget::Staged).unwrap();
-        assert_eq!(diff.path, "new.txt");
-        assert!(matches!(&diff.content, DiffContent::Text { hunks } if hunks.len() == 1));
-
-        let DiffContent::Text { hunks } = &diff.content else {
-            unreachable!()
-        };
-        let hunk = &hunks[0];
-        assert_eq!(hunk.old_start, 0);
-        assert_eq!(hunk.new_start, 1);
-        assert_eq!(hunk.lines.len(), 3);
-        assert!(
-            hunk.lines
-                .iter()
-                .all(|l| l.change_type == LineChangeType::Addition)
-        );
+        fs::write(root.join("added.txt"), "newer\n").unwrap();
+        fs::remove_file(root.join("scratch.txt")).unwrap();
+        fs::write(root.join("renamed.txt"), "a\nb\nc\nd\ne\n").unwrap();
+        fs::remove_file(root.join("removed.txt")).unwrap();
+        fs::write(root.join("untracked.txt"), "u\n").unwrap();
+
+        let status = get_status(root).unwrap();
+        let mut merged: Vec<(&str, &FileEntryKind)> = status
+            .uncommitted
+            .iter()
+            .map(|e| (e.path.as_str(), &e.kind))
+            .collect();
+        merged.sort_by_key(|(path, _)| *path);
+
+        assert_eq!(merged.len(), 5);
+        assert_eq!(merged[0], ("added.txt", &FileEntryKind::Added));
+        assert_eq!(merged[1], ("edited.txt", &FileEntryKind::Modified));
+        assert_eq!(merged[2], ("removed.txt", &FileEntryKind::Deleted));
+        assert_eq!(merged[3].0, "renamed.txt");
+        assert!(matches!(
+            merged[3].1,
+            FileEntryKind::Renamed { old_path, .. } if old_path == "moved.txt"
+        ));
+        assert_eq!(merged[4], ("untracked.txt", &FileEntryKind::Untracked));
     }
 
     #[test]
-    fn test_get_git_file_contents_staged_new_file() {
+    fn test_get_status_reports_line_stats() {
         let (temp_dir, repo) = create_test_repo();
+        commit_file(&repo, "file.txt", "a\nb\nc\n", "Initial commit");
 
-        commit_file(&repo, "existing.txt", "existing", "Initial commit");
+        fs::write(temp_dir.path().join("file.txt"), "a\nB\nc\nd\n").unwrap();
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new("file.txt")).unwrap();
+        index.write().unwrap();
+        fs::write(temp_dir.path().join("file.txt"), "a\nB\nc\n").unwrap();
+        fs::write(temp_dir.path().join("new.txt"), "1\n2\n").unwrap();
+        fs::write(temp_dir.path().join("image.bin"), [0u8, 1, 2, 0]).unwrap();
+
+        assert!(
+            get_status(temp_dir.path()).unwrap().staged[0]
+                .stats
+                .is_none()
+        );
+        let status = get_status_with_stats(temp_dir.path()).unwrap();
+        let stats_for = |entries: &[FileEntry], path: &str| {
+            entries
+                .iter()
+                .find(|e| e.path == path)
+                .and_then(|e| e.stats)
+                .unwrap()
+        };
+
+        let staged = stats_for(&status.staged, "file.txt");
+        assert_eq!((staged.insertions, staged.deletions), (2, 1));
+        let unstaged = stats_for(&status.unstaged, "file.txt");
+        assert_eq!((unstaged.insertions, unstaged.deletions), (0, 1));
+        let untracked = stats_for(&status.untracked, "new.txt");
+        assert_eq!((untracked.insertions, untracked.is_binary), (2, false));
+        assert!(stats_for(&status.untracked, "image.bin").is_binary);
+    }
+
+    #[test]
+    fn test_get_changeset_diff_respects_line_budget() {
+        let (temp_dir, repo) = create_test_repo();
+        for name in ["a.txt", "b.txt", "c.txt"] {
+            commit_file(&repo, name, "one\ntwo\nthree\n", "Add file");
+            fs::write(temp_dir.path().join(name), "ONE\ntwo\nthree\n").unwrap();
+        }
+        let options = DiffOptions::default();
+
+        let full =
+            get_changeset_diff(temp_dir.path(), &DiffTarget::Unstaged, &options, None).unwrap();
+        assert!(!full.truncated);
+        let paths: Vec<&str> = full.files.iter().map(|f| f.diff.path.as_str()).collect();
+        assert_eq!(paths, vec!["a.txt", "b.txt", "c.txt"]);
+
+        let bounded =
+            get_changeset_diff(temp_dir.path(), &DiffTarget::Unstaged, &options, Some(6)).unwrap();
+        assert!(bounded.truncated);
+        let truncated: Vec<bool> = bounded.files.iter().map(|f| f.truncated).collect();
+        assert_eq!(truncated, vec![false, true, true]);
+        assert_eq!(bounded.files[0].diff, full.files[0].diff);
+        assert_eq!(
+            bounded.files[1].diff.content,
+            DiffContent::Text { hunks: Vec::new() }
+        );
+    }
+
+    #[test]
+    fn test_get_changeset_diff_fits_later_files_after_truncation() {
+        let (temp_dir, repo) = create_test_repo();
+        commit_file(&repo, "a.txt", "one\ntwo\nthree\n", "Add a");
+        commit_file(&repo, "b.txt", "one\n", "Add b");
+        fs::write(temp_dir.path().join("a.txt"), "ONE\nTWO\nTHREE\n").unwrap();
+        fs::write(temp_dir.path().join("b.txt"), "ONE\n").unwrap();
+        let options = DiffOptions::default();
+
+        let bounded =
+            get_changeset_diff(temp_dir.path(), &DiffTarget::Unstaged, &options, Some(5)).unwrap();
+        assert!(bounded.truncated);
+        let truncated: Vec<bool> = bounded.files.iter().map(|f| f.truncated).collect();
+        assert_eq!(truncated, vec![true, false]);
+        let DiffContent::Text { hunks } = &bounded.files[1].diff.content else {
+            unreachable!()
+        };
+        assert_eq!(hunks.len(), 1);
+        assert_eq!(hunks[0].lines.len(), 2);
+    }
+
+    #[test]
+    fn test_get_file_diff_staged_new_file() {
+        let (temp_dir, repo) = create_test_repo();
+
+        commit_file(&repo, "existing.txt", "existing", "Initial commit");
+
+        fs::write(temp_dir.path().join("new.txt"), "line 1\nline 2\nline 3\n").unwrap();
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new("new.txt")).unwrap();
+        index.write().unwrap();
+
+        let diff = get_file_diff(
+            temp_dir.path(),
+            "new.txt",
+            &DiffTarget::Staged,
+            &DiffOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(diff.path, "new.txt");
+        assert!(matches!(&diff.content, DiffContent::Text { hunks } if hunks.len() == 1));
+
+        let DiffContent::Text { hunks } = &diff.content else {
+            unreachable!()
+        };
+        let hunk = &hunks[0];
+        assert_eq!(hunk.old_start, 0);
+        assert_eq!(hunk.new_start, 1);
+        assert_eq!(hunk.lines.len(), 3);
+        assert!(
+            hunk.lines
+                .iter()
+                .all(|l| l.change_type == LineChangeType::Addition)
+        );
+    }
+
+    #[test]
+    fn test_get_git_file_contents_staged_new_file() {
+        let (temp_dir, repo) = create_test_repo();
+
+        commit_file(&repo, "existing.txt", "existing", "Initial commit");
 
         let new_content = "line 1\nline 2\nline 3\n";
         fs::write(temp_dir.path().join("new.ts"), new_content).unwrap();
@@ -585,7 +2001,7 @@ mod tests {
         index.write().unwrap();
 
         let contents =
-            get_git_file_contents(temp_dir.path(), "new.ts", DiffTarget::Staged).unwrap();
+            get_git_file_contents(temp_dir.path(), "new.ts", &DiffTarget::Staged).unwrap();
 
         assert!(contents.old_file.content.is_none());
         assert_eq!(
@@ -597,4 +2013,850 @@ mod tests {
         assert_eq!(contents.old_file.lang, Some("typescript".to_string()));
         assert_eq!(contents.new_file.lang, Some("typescript".to_string()));
     }
+
+    #[test]
+    fn test_diff_target_from_revspec() {
+        assert_eq!(
+            DiffTarget::from_revspec("main..feature"),
+            DiffTarget::Range {
+                from: Some("main".to_string()),
+                to: "feature".to_string()
+            }
+        );
+        assert_eq!(
+            DiffTarget::from_revspec("HEAD~3.."),
+            DiffTarget::Range {
+                from: Some("HEAD~3".to_string()),
+                to: "HEAD".to_string()
+            }
+        );
+        assert_eq!(
+            DiffTarget::from_revspec("abc123"),
+            DiffTarget::Range {
+                from: None,
+                to: "abc123".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_get_file_diff_range_between_commits() {
+        let (temp_dir, repo) = create_test_repo();
+
+        commit_file(&repo, "file.txt", "one\n", "First");
+        commit_file(&repo, "file.txt", "one\ntwo\n", "Second");
+        commit_file(&repo, "file.txt", "one\ntwo\nthree\n", "Third");
+
+        let target = DiffTarget::from_revspec("HEAD~2..HEAD");
+        let diff = get_file_diff(
+            temp_dir.path(),
+            "file.txt",
+            &target,
+            &DiffOptions::default(),
+        )
+        .unwrap();
+
+        let DiffContent::Text { hunks } = &diff.content else {
+            unreachable!()
+        };
+        let added: Vec<&str> = hunks[0]
+            .lines
+            .iter()
+            .filter(|l| l.change_type == LineChangeType::Addition)
+            .map(|l| l.content.as_str())
+            .collect();
+        assert_eq!(added, vec!["two", "three"]);
+    }
+
+    #[test]
+    fn test_get_file_diff_single_commit_against_parent() {
+        let (temp_dir, repo) = create_test_repo();
+
+        commit_file(&repo, "file.txt", "one\n", "First");
+        commit_file(&repo, "file.txt", "one\ntwo\n", "Second");
+
+        let target = DiffTarget::from_revspec("HEAD");
+        let diff = get_file_diff(
+            temp_dir.path(),
+            "file.txt",
+            &target,
+            &DiffOptions::default(),
+        )
+        .unwrap();
+
+        let DiffContent::Text { hunks } = &diff.content else {
+            unreachable!()
+        };
+        let added: Vec<&str> = hunks[0]
+            .lines
+            .iter()
+            .filter(|l| l.change_type == LineChangeType::Addition)
+            .map(|l| l.content.as_str())
+            .collect();
+        assert_eq!(added, vec!["two"]);
+    }
+
+    #[test]
+    fn test_get_changed_files_range_root_commit() {
+        let (temp_dir, repo) = create_test_repo();
+
+        commit_file(&repo, "a.txt", "a", "First");
+        commit_file(&repo, "b.txt", "b", "Second");
+
+        let root = get_changed_files(
+            temp_dir.path(),
+            &DiffTarget::from_revspec("HEAD~1"),
+            &DiffOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(root.len(), 1);
+        assert_eq!(root[0].path, "a.txt");
+        assert_eq!(root[0].kind, FileEntryKind::Added);
+
+        let range = get_changed_files(
+            temp_dir.path(),
+            &DiffTarget::from_revspec("HEAD~1..HEAD"),
+            &DiffOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(range.len(), 1);
+        assert_eq!(range[0].path, "b.txt");
+    }
+
+    #[test]
+    fn test_get_git_file_contents_range() {
+        let (temp_dir, repo) = create_test_repo();
+
+        commit_file(&repo, "file.rs", "old", "First");
+        commit_file(&repo, "file.rs", "new", "Second");
+        fs::write(temp_dir.path().join("file.rs"), "workdir").unwrap();
+
+        let contents = get_git_file_contents(
+            temp_dir.path(),
+            "file.rs",
+            &DiffTarget::from_revspec("HEAD"),
+        )
+        .unwrap();
+
+        assert_eq!(
+            contents.old_file.content,
+            Some(FileContent::Text {
+                contents: "old".to_string()
+            })
+        );
+        assert_eq!(
+            contents.new_file.content,
+            Some(FileContent::Text {
+                contents: "new".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn test_diff_target_from_revspec_three_dot() {
+        assert_eq!(
+            DiffTarget::from_revspec("main...feature"),
+            DiffTarget::MergeBase {
+                base: "main".to_string(),
+                head: "feature".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_merge_base_ignores_changes_on_base_branch() {
+        let (temp_dir, repo) = create_test_repo();
+
+        commit_file(&repo, "shared.txt", "shared", "Initial commit");
+        let fork = repo.head().unwrap().peel_to_commit().unwrap();
+        repo.branch("feature", &fork, false).unwrap();
+
+        commit_file(&repo, "main_only.txt", "main", "Main work");
+        let main = repo.head().unwrap().peel_to_commit().unwrap();
+        repo.branch("base", &main, false).unwrap();
+
+        repo.set_head("refs/heads/feature").unwrap();
+        repo.checkout_head(Some(git2::build::CheckoutBuilder::new().force()))
+            .unwrap();
+        commit_file(&repo, "feature.txt", "feature", "Feature work");
+
+        let target = DiffTarget::MergeBase {
+            base: "base".to_string(),
+            head: "feature".to_string(),
+        };
+        let files = get_changed_files(temp_dir.path(), &target, &DiffOptions::default()).unwrap();
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].path, "feature.txt");
+        assert_eq!(files[0].kind, FileEntryKind::Added);
+
+        let two_dot = get_changed_files(
+            temp_dir.path(),
+            &DiffTarget::from_revspec("base..feature"),
+            &DiffOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(two_dot.len(), 2);
+
+        let diff = get_file_diff(
+            temp_dir.path(),
+            "feature.txt",
+            &target,
+            &DiffOptions::default(),
+        )
+        .unwrap();
+        assert!(matches!(&diff.content, DiffContent::Text { hunks } if hunks.len() == 1));
+    }
+
+    #[test]
+    fn test_uncommitted_merges_staged_and_unstaged_changes() {
+        let (temp_dir, repo) = create_test_repo();
+
+        commit_file(&repo, "file.txt", "one\ntwo\n", "Initial commit");
+
+        fs::write(temp_dir.path().join("file.txt"), "one staged\ntwo\n").unwrap();
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new("file.txt")).unwrap();
+        index.write().unwrap();
+        fs::write(
+            temp_dir.path().join("file.txt"),
+            "one staged\ntwo unstaged\n",
+        )
+        .unwrap();
+        fs::write(temp_dir.path().join("new.txt"), "brand new\n").unwrap();
+
+        let status = get_status(temp_dir.path()).unwrap();
+        assert_eq!(status.staged.len(), 1);
+        assert_eq!(status.unstaged.len(), 1);
+
+        let files = get_changed_files(
+            temp_dir.path(),
+            &DiffTarget::Uncommitted,
+            &DiffOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(files.len(), 2);
+        assert!(
+            files
+                .iter()
+                .any(|f| f.path == "file.txt" && f.kind == FileEntryKind::Modified)
+        );
+        assert!(
+            files
+                .iter()
+                .any(|f| f.path == "new.txt" && f.kind == FileEntryKind::Untracked)
+        );
+
+        let diff = get_file_diff(
+            temp_dir.path(),
+            "file.txt",
+            &DiffTarget::Uncommitted,
+            &DiffOptions::default(),
+        )
+        .unwrap();
+        let DiffContent::Text { hunks } = &diff.content else {
+            unreachable!()
+        };
+        let added: Vec<&str> = hunks
+            .iter()
+            .flat_map(|h| &h.lines)
+            .filter(|l| l.change_type == LineChangeType::Addition)
+            .map(|l| l.content.as_str())
+            .collect();
+        assert_eq!(added, vec!["one staged", "two unstaged"]);
+
+        let contents =
+            get_git_file_contents(temp_dir.path(), "file.txt", &DiffTarget::Uncommitted).unwrap();
+        assert_eq!(
+            contents.old_file.content,
+            Some(FileContent::Text {
+                contents: "one\ntwo\n".to_string()
+            })
+        );
+        assert_eq!(
+            contents.new_file.content,
+            Some(FileContent::Text {
+                contents: "one staged\ntwo unstaged\n".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn test_uncommitted_diff_includes_untracked_content() {
+        let (temp_dir, repo) = create_test_repo();
+
+        commit_file(&repo, "existing.txt", "existing", "Initial commit");
+        fs::write(temp_dir.path().join("new.txt"), "a\nb\n").unwrap();
+
+        let diff = get_file_diff(
+            temp_dir.path(),
+            "new.txt",
+            &DiffTarget::Uncommitted,
+            &DiffOptions::default(),
+        )
+        .unwrap();
+        let DiffContent::Text { hunks } = &diff.content else {
+            unreachable!()
+        };
+        assert_eq!(hunks.len(), 1);
+        assert_eq!(hunks[0].lines.len(), 2);
+    }
+
+    #[test]
+    fn test_get_file_diff_unstaged_untracked_file() {
+        let (temp_dir, repo) = create_test_repo();
+
+        commit_file(&repo, "existing.txt", "existing", "Initial commit");
+        fs::create_dir_all(temp_dir.path().join("new_dir/nested")).unwrap();
+        fs::write(
+            temp_dir.path().join("new_dir/nested/file.txt"),
+            "line 1\nline 2\n",
+        )
+        .unwrap();
+
+        let status = get_status(temp_dir.path()).unwrap();
+        assert_eq!(status.untracked[0].path, "new_dir/nested/file.txt");
+
+        let diff = get_file_diff(
+            temp_dir.path(),
+            "new_dir/nested/file.txt",
+            &DiffTarget::Unstaged,
+            &DiffOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(diff.path, "new_dir/nested/file.txt");
+        let DiffContent::Text { hunks } = &diff.content else {
+            unreachable!()
+        };
+        assert_eq!(hunks.len(), 1);
+        assert_eq!(hunks[0].old_start, 0);
+        assert_eq!(hunks[0].lines.len(), 2);
+        assert!(
+            hunks[0]
+                .lines
+                .iter()
+                .all(|l| l.change_type == LineChangeType::Addition)
+        );
+    }
+
+    #[test]
+    fn test_get_file_diff_unstaged_untracked_binary() {
+        let (temp_dir, repo) = create_test_repo();
+
+        commit_file(&repo, "existing.txt", "existing", "Initial commit");
+        fs::write(temp_dir.path().join("image.bin"), [0u8, 1, 2, 3]).unwrap();
+
+        let diff = get_file_diff(
+            temp_dir.path(),
+            "image.bin",
+            &DiffTarget::Unstaged,
+            &DiffOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(diff.content, DiffContent::Binary);
+    }
+
+    #[test]
+    fn test_get_file_diff_context_lines_option() {
+        let (temp_dir, repo) = create_test_repo();
+
+        let original = (1..=20)
+            .map(|i| format!("line {i}\n"))
+            .collect::<Vec<_>>()
+            .concat();
+        commit_file(&repo, "file.txt", &original, "Initial commit");
+        fs::write(
+            temp_dir.path().join("file.txt"),
+            original.replace("line 10\n", "line ten\n"),
+        )
+        .unwrap();
+
+        let options = DiffOptions {
+            context_lines: 1,
+            ..DiffOptions::default()
+        };
+        let diff =
+            get_file_diff(temp_dir.path(), "file.txt", &DiffTarget::Unstaged, &options).unwrap();
+        let DiffContent::Text { hunks } = &diff.content else {
+            unreachable!()
+        };
+        assert_eq!(hunks[0].old_start, 9);
+        assert_eq!(hunks[0].lines.len(), 4);
+    }
+
+    #[test]
+    fn test_get_file_diff_ignore_whitespace_change() {
+        let (temp_dir, repo) = create_test_repo();
+
+        commit_file(
+            &repo,
+            "file.rs",
+            "fn main() {\n    run();\n}\n",
+            "Initial commit",
+        );
+        fs::write(
+            temp_dir.path().join("file.rs"),
+            "fn main() {\n        run();\n}\n",
+        )
+        .unwrap();
+
+        let noisy = get_file_diff(
+            temp_dir.path(),
+            "file.rs",
+            &DiffTarget::Unstaged,
+            &DiffOptions::default(),
+        )
+        .unwrap();
+        assert!(matches!(&noisy.content, DiffContent::Text { hunks } if hunks.len() == 1));
+
+        let options = DiffOptions {
+            whitespace: WhitespaceMode::IgnoreChange,
+            algorithm: DiffAlgorithm::Patience,
+            ..DiffOptions::default()
+        };
+        let quiet =
+            get_file_diff(temp_dir.path(), "file.rs", &DiffTarget::Unstaged, &options).unwrap();
+        assert!(matches!(&quiet.content, DiffContent::Text { hunks } if hunks.is_empty()));
+    }
+
+    #[test]
+    fn test_get_context_lines_between_hunks() {
+        let (temp_dir, repo) = create_test_repo();
+
+        let original = (1..=30)
+            .map(|i| format!("line {i}\n"))
+            .collect::<Vec<_>>()
+            .concat();
+        commit_file(&repo, "file.txt", &original, "Initial commit");
+        let modified = original
+            .replace("line 2\n", "line 2\ninserted a\ninserted b\n")
+            .replace("line 25\n", "");
+        fs::write(temp_dir.path().join("file.txt"), modified).unwrap();
+
+        let lines = get_context_lines(
+            temp_dir.path(),
+            "file.txt",
+            &DiffTarget::Unstaged,
+            DiffSide::Old,
+            10,
+            12,
+            &DiffOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0].content, "line 10");
+        assert_eq!(lines[0].old_line_no, Some(10));
+        assert_eq!(lines[0].new_line_no, Some(12));
+        assert!(
+            lines
+                .iter()
+                .all(|l| l.change_type == LineChangeType::Context)
+        );
+
+        let tail = get_context_lines(
+            temp_dir.path(),
+            "file.txt",
+            &DiffTarget::Unstaged,
+            DiffSide::New,
+            28,
+            100,
+            &DiffOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(tail.len(), 4);
+        assert_eq!(tail[0].content, "line 27");
+        assert_eq!(tail[0].old_line_no, Some(27));
+        assert_eq!(tail[3].new_line_no, Some(31));
+    }
+
+    #[test]
+    fn test_get_context_lines_follows_rename() {
+        let (temp_dir, repo) = create_test_repo();
+
+        let original = (1..=30)
+            .map(|i| format!("line {i}\n"))
+            .collect::<Vec<_>>()
+            .concat();
+        commit_file(&repo, "old.txt", &original, "Initial commit");
+        fs::remove_file(temp_dir.path().join("old.txt")).unwrap();
+        fs::write(
+            temp_dir.path().join("new.txt"),
+            original.replace("line 15\n", "line fifteen\n"),
+        )
+        .unwrap();
+        let mut index = repo.index().unwrap();
+        index.remove_path(Path::new("old.txt")).unwrap();
+        index.add_path(Path::new("new.txt")).unwrap();
+        index.write().unwrap();
+
+        let lines = get_context_lines(
+            temp_dir.path(),
+            "new.txt",
+            &DiffTarget::Staged,
+            DiffSide::New,
+            1,
+            3,
+            &DiffOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(lines.len(), 3);
+        assert!(
+            lines
+                .iter()
+                .all(|l| l.change_type == LineChangeType::Context)
+        );
+        assert_eq!(lines[2].old_line_no, Some(3));
+
+        let contents =
+            get_git_file_contents(temp_dir.path(), "new.txt", &DiffTarget::Staged).unwrap();
+        assert_eq!(contents.old_file.name, "old.txt");
+        assert_eq!(
+            contents.old_file.content,
+            Some(FileContent::Text { contents: original })
+        );
+    }
+
+    #[test]
+    fn test_get_context_lines_marks_changed_lines() {
+        let (temp_dir, repo) = create_test_repo();
+
+        commit_file(&repo, "file.txt", "a\nb\nc\n", "Initial commit");
+        fs::write(temp_dir.path().join("file.txt"), "a\nB\nc\n").unwrap();
+
+        let lines = get_context_lines(
+            temp_dir.path(),
+            "file.txt",
+            &DiffTarget::Unstaged,
+            DiffSide::Old,
+            1,
+            3,
+            &DiffOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(lines[1].change_type, LineChangeType::Deletion);
+        assert_eq!(lines[1].new_line_no, None);
+        assert_eq!(lines[2].old_line_no, Some(3));
+        assert_eq!(lines[2].new_line_no, Some(3));
+    }
+
+    #[test]
+    fn test_get_file_diff_includes_inline_changes() {
+        let (temp_dir, repo) = create_test_repo();
+
+        commit_file(&repo, "file.rs", "let total = 1;\n", "Initial commit");
+        fs::write(temp_dir.path().join("file.rs"), "let sum = 1;\n").unwrap();
+
+        let diff = get_file_diff(
+            temp_dir.path(),
+            "file.rs",
+            &DiffTarget::Unstaged,
+            &DiffOptions::default(),
+        )
+        .unwrap();
+        let DiffContent::Text { hunks } = &diff.content else {
+            unreachable!()
+        };
+        let deletion = &hunks[0].lines[0];
+        assert_eq!(deletion.change_type, LineChangeType::Deletion);
+        assert_eq!(
+            deletion.inline_changes,
+            vec![InlineChange { start: 4, end: 9 }]
+        );
+    }
+
+    #[test]
+    fn test_get_file_diff_detects_staged_rename_with_edit() {
+        let (temp_dir, repo) = create_test_repo();
+
+        let original = (1..=10)
+            .map(|i| format!("line {i}\n"))
+            .collect::<Vec<_>>()
+            .concat();
+        commit_file(&repo, "old.txt", &original, "Initial commit");
+
+        fs::remove_file(temp_dir.path().join("old.txt")).unwrap();
+        fs::write(
+            temp_dir.path().join("new.txt"),
+            original.replace("line 5\n", "line five\n"),
+        )
+        .unwrap();
+        let mut index = repo.index().unwrap();
+        index.remove_path(Path::new("old.txt")).unwrap();
+        index.add_path(Path::new("new.txt")).unwrap();
+        index.write().unwrap();
+
+        let status = get_status(temp_dir.path()).unwrap();
+        assert_eq!(
+            status.staged[0].kind,
+            FileEntryKind::Renamed {
+                old_path: "old.txt".to_string(),
+                similarity: 90
+            }
+        );
+
+        let diff = get_file_diff(
+            temp_dir.path(),
+            "new.txt",
+            &DiffTarget::Staged,
+            &DiffOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(diff.old_path, Some("old.txt".to_string()));
+        assert_eq!(diff.similarity, Some(90));
+        let DiffContent::Text { hunks } = &diff.content else {
+            unreachable!()
+        };
+        assert_eq!(hunks.len(), 1);
+        assert_eq!(
+            hunks[0]
+                .lines
+                .iter()
+                .filter(|l| l.change_type == LineChangeType::Addition)
+                .count(),
+            1
+        );
+
+        let options = DiffOptions {
+            renames: RenameDetection::Off,
+            ..DiffOptions::default()
+        };
+        let plain =
+            get_file_diff(temp_dir.path(), "new.txt", &DiffTarget::Staged, &options).unwrap();
+        assert_eq!(plain.old_path, None);
+        assert_eq!(plain.similarity, None);
+    }
+
+    #[test]
+    fn test_similarity_matches_rename_pairing() {
+        let (temp_dir, repo) = create_test_repo();
+        let lines: Vec<String> = (1..=20).map(|i| format!("line {i}\n")).collect();
+        commit_file(&repo, "old.txt", &lines.concat(), "Initial commit");
+        commit_file(&repo, "gone.txt", "unrelated\n", "Add gone");
+
+        let reordered: String = lines.iter().rev().map(String::as_str).collect();
+        fs::remove_file(temp_dir.path().join("old.txt")).unwrap();
+        fs::remove_file(temp_dir.path().join("gone.txt")).unwrap();
+        fs::write(temp_dir.path().join("new.txt"), reordered).unwrap();
+        let mut index = repo.index().unwrap();
+        index.remove_path(Path::new("old.txt")).unwrap();
+        index.remove_path(Path::new("gone.txt")).unwrap();
+        index.add_path(Path::new("new.txt")).unwrap();
+        index.write().unwrap();
+
+        let options = DiffOptions {
+            similarity_threshold: 60,
+            ..DiffOptions::default()
+        };
+        let renamed_score = |kind: &FileEntryKind| match kind {
+            FileEntryKind::Renamed {
+                old_path,
+                similarity,
+            } if old_path == "old.txt" => Some(*similarity),
+            _ => None,
+        };
+
+        let status = get_status(temp_dir.path()).unwrap();
+        let staged = status.staged.iter().find(|e| e.path == "new.txt").unwrap();
+        assert!(renamed_score(&staged.kind).unwrap() >= options.similarity_threshold);
+
+        let diff =
+            get_file_diff(temp_dir.path(), "new.txt", &DiffTarget::Staged, &options).unwrap();
+        assert_eq!(diff.old_path.as_deref(), Some("old.txt"));
+        assert!(diff.similarity.unwrap() >= options.similarity_threshold);
+        let from_old =
+            get_file_diff(temp_dir.path(), "old.txt", &DiffTarget::Staged, &options).unwrap();
+        assert_eq!(from_old.path, "new.txt");
+        assert_eq!(from_old.similarity, diff.similarity);
+
+        let changed = get_changed_files(temp_dir.path(), &DiffTarget::Staged, &options).unwrap();
+        let renamed = changed.iter().find(|e| e.path == "new.txt").unwrap();
+        assert_eq!(renamed_score(&renamed.kind), diff.similarity);
+        assert!(changed.iter().any(|e| e.path == "gone.txt"));
+    }
+
+    #[test]
+    fn test_get_changed_files_detects_copies() {
+        let (temp_dir, repo) = create_test_repo();
+
+        let original = (1..=10)
+            .map(|i| format!("line {i}\n"))
+            .collect::<Vec<_>>()
+            .concat();
+        commit_file(&repo, "source.txt", &original, "Initial commit");
+        fs::write(
+            temp_dir.path().join("source.txt"),
+            original.replace("line 1\n", "line one\n"),
+        )
+        .unwrap();
+        commit_file(&repo, "copy.txt", &original, "Copy");
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new("source.txt")).unwrap();
+        index.write().unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let sig = Signature::now("Test User", "test@example.com").unwrap();
+        let parent = repo.head().unwrap().peel_to_commit().unwrap();
+        let amended = parent
+            .amend(Some("HEAD"), None, Some(&sig), None, None, Some(&tree))
+            .unwrap();
+        assert!(!amended.is_zero());
+
+        let options = DiffOptions {
+            renames: RenameDetection::RenamesAndCopies,
+            ..DiffOptions::default()
+        };
+        let files = get_changed_files(temp_dir.path(), &DiffTarget::from_revspec("HEAD"), &options)
+            .unwrap();
+        let copy = files.iter().find(|f| f.path == "copy.txt").unwrap();
+        assert_eq!(
+            copy.kind,
+            FileEntryKind::Copied {
+                old_path: "source.txt".to_string(),
+                similarity: 100
+            }
+        );
+    }
+
+    #[test]
+    fn test_scores_renames_and_copies_from_paired_diff() {
+        let (temp_dir, repo) = create_test_repo();
+
+        let numbered = |name: &str| {
+            (1..=10)
+                .map(|i| format!("{name} {i}\n"))
+                .collect::<Vec<_>>()
+                .concat()
+        };
+        commit_file(&repo, "source.txt", &numbered("source"), "Add source");
+        commit_file(&repo, "old.txt", &numbered("old"), "Add old");
+
+        fs::write(
+            temp_dir.path().join("source.txt"),
+            numbered("source").replace("source 1\n", "source one\n"),
+        )
+        .unwrap();
+        fs::write(
+            temp_dir.path().join("copy.txt"),
+            numbered("source").replace("source 5\n", "source five\n"),
+        )
+        .unwrap();
+        fs::remove_file(temp_dir.path().join("old.txt")).unwrap();
+        fs::write(
+            temp_dir.path().join("new.txt"),
+            numbered("old").replace("old 5\n", "old five\n"),
+        )
+        .unwrap();
+        let mut index = repo.index().unwrap();
+        index.remove_path(Path::new("old.txt")).unwrap();
+        for path in ["source.txt", "copy.txt", "new.txt"] {
+            index.add_path(Path::new(path)).unwrap();
+        }
+        index.write().unwrap();
+        commit_index(&repo, "Copy and rename");
+
+        let options = DiffOptions {
+            renames: RenameDetection::RenamesAndCopies,
+            ..DiffOptions::default()
+        };
+        let target = DiffTarget::from_revspec("HEAD");
+        let files = get_changed_files(temp_dir.path(), &target, &options).unwrap();
+        let kind_of = |path: &str| files.iter().find(|f| f.path == path).unwrap().kind.clone();
+        assert_eq!(
+            kind_of("copy.txt"),
+            FileEntryKind::Copied {
+                old_path: "source.txt".to_string(),
+                similarity: 90
+            }
+        );
+        assert_eq!(
+            kind_of("new.txt"),
+            FileEntryKind::Renamed {
+                old_path: "old.txt".to_string(),
+                similarity: 90
+            }
+        );
+
+        let changeset = get_changeset_diff(temp_dir.path(), &target, &options, None).unwrap();
+        let similarity_of = |path: &str| {
+            changeset
+                .files
+                .iter()
+                .find(|f| f.diff.path == path)
+                .and_then(|f| f.diff.similarity)
+        };
+        assert_eq!(similarity_of("copy.txt"), Some(90));
+        assert_eq!(similarity_of("new.txt"), Some(90));
+    }
+
+    fn blamed_summary(blame: &LineBlame) -> Option<&str> {
+        match blame {
+            LineBlame::Committed { summary, .. } => Some(summary),
+            LineBlame::Uncommitted => None,
+        }
+    }
+
+    #[test]
+    fn test_blame_file_at_revision_and_working_tree() {
+        let (temp_dir, repo) = create_test_repo();
+
+        commit_file(&repo, "file.txt", "one\ntwo\n", "First");
+        commit_file(&repo, "file.txt", "one\ntwo\nthree\n", "Second");
+        fs::write(temp_dir.path().join("file.txt"), "one\nTWO\nthree\n").unwrap();
+
+        let at_head = blame_file(temp_dir.path(), "file.txt", Some("HEAD")).unwrap();
+        let summaries: Vec<_> = at_head.iter().map(blamed_summary).collect();
+        assert_eq!(
+            summaries,
+            vec![Some("First"), Some("First"), Some("Second")]
+        );
+
+        let working = blame_file(temp_dir.path(), "file.txt", None).unwrap();
+        let summaries: Vec<_> = working.iter().map(blamed_summary).collect();
+        assert_eq!(summaries, vec![Some("First"), None, Some("Second")]);
+        let LineBlame::Committed { author, .. } = &working[0] else {
+            unreachable!()
+        };
+        assert_eq!(author.name, "Test User");
+    }
+
+    #[test]
+    fn test_blame_file_untracked_is_uncommitted() {
+        let (temp_dir, repo) = create_test_repo();
+
+        commit_file(&repo, "file.txt", "one\n", "First");
+        fs::write(temp_dir.path().join("new.txt"), "a\nb\n").unwrap();
+
+        let blame = blame_file(temp_dir.path(), "new.txt", None).unwrap();
+        assert_eq!(blame, vec![LineBlame::Uncommitted, LineBlame::Uncommitted]);
+    }
+
+    #[test]
+    fn test_get_file_diff_blames_deletions() {
+        let (temp_dir, repo) = create_test_repo();
+
+        commit_file(&repo, "file.txt", "one\ntwo\n", "First");
+        commit_file(&repo, "file.txt", "one\ntwo\nthree\n", "Second");
+        fs::write(temp_dir.path().join("file.txt"), "one\n").unwrap();
+
+        let options = DiffOptions {
+            blame_deletions: true,
+            ..DiffOptions::default()
+        };
+        let diff =
+            get_file_diff(temp_dir.path(), "file.txt", &DiffTarget::Unstaged, &options).unwrap();
+        let DiffContent::Text { hunks } = &diff.content else {
+            unreachable!()
+        };
+        let blamed: Vec<_> = hunks[0]
+            .lines
+            .iter()
+            .map(|l| (l.change_type, l.blame.as_ref().and_then(blamed_summary)))
+            .collect();
+        assert_eq!(
+            blamed,
+            vec![
+                (LineChangeType::Context, None),
+                (LineChangeType::Deletion, Some("First")),
+                (LineChangeType::Deletion, Some("Second")),
+            ]
+        );
+    }
 }
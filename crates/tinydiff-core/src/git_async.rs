@@ -1,18 +1,60 @@
+use crate::commit;
+use crate::conflict;
+use crate::discard;
 use crate::error::CoreError;
 use crate::git;
-use crate::types::{DiffTarget, FileDiff, GitFileContents, GitStatus};
+use crate::history;
+use crate::staging;
+use crate::stash;
+use crate::submodule;
+use crate::types::{
+    ChangesetDiff, CommitInfo, ConflictContents, ConflictResolution, DiffLine, DiffOptions,
+    DiffSide, DiffTarget, DiscardRecord, FileDiff, FileEntry, FileHistoryPage, GitFileContents,
+    GitStatus, HunkSelection, LineBlame, LogOptions, LogPage, PatchAction, StashEntry,
+    SubmoduleDiff, WorktreeInfo,
+};
+use crate::worktree;
 use std::path::PathBuf;
 
 pub async fn get_status_async(repo_path: PathBuf) -> Result<GitStatus, CoreError> {
     tokio::task::spawn_blocking(move || git::get_status(&repo_path)).await?
 }
 
+pub async fn get_status_with_stats_async(repo_path: PathBuf) -> Result<GitStatus, CoreError> {
+    tokio::task::spawn_blocking(move || git::get_status_with_stats(&repo_path)).await?
+}
+
+pub async fn get_changed_files_async(
+    repo_path: PathBuf,
+    target: DiffTarget,
+    options: DiffOptions,
+) -> Result<Vec<FileEntry>, CoreError> {
+    tokio::task::spawn_blocking(move || git::get_changed_files(&repo_path, &target, &options))
+        .await?
+}
+
 pub async fn get_file_diff_async(
     repo_path: PathBuf,
     file_path: String,
     target: DiffTarget,
+    options: DiffOptions,
 ) -> Result<FileDiff, CoreError> {
-    tokio::task::spawn_blocking(move || git::get_file_diff(&repo_path, &file_path, target)).await?
+    tokio::task::spawn_blocking(move || {
+        git::get_file_diff(&repo_path, &file_path, &target, &options)
+    })
+    .await?
+}
+
+pub async fn get_changeset_diff_async(
+    repo_path: PathBuf,
+    target: DiffTarget,
+    options: DiffOptions,
+    max_lines: Option<u32>,
+) -> Result<ChangesetDiff, CoreError> {
+    tokio::task::spawn_blocking(move || {
+        git::get_changeset_diff(&repo_path, &target, &options, max_lines)
+    })
+    .await?
 }
 
 pub async fn get_git_file_contents_async(
@@ -20,27 +62,176 @@ pub async fn get_git_file_contents_async(
     file_path: String,
     target: DiffTarget,
 ) -> Result<GitFileContents, CoreError> {
-    tokio::task::spawn_blocking(move || git::get_git_file_contents(&repo_path, &file_path, target))
+    tokio::task::spawn_blocking(move || git::get_git_file_contents(&repo_path, &file_path, &target))
+        .await?
+}
+
+pub async fn get_context_lines_async(
+    repo_path: PathBuf,
+    file_path: String,
+    target: DiffTarget,
+    side: DiffSide,
+    start: u32,
+    end: u32,
+    options: DiffOptions,
+) -> Result<Vec<DiffLine>, CoreError> {
+    tokio::task::spawn_blocking(move || {
+        git::get_context_lines(&repo_path, &file_path, &target, side, start, end, &options)
+    })
+    .await?
+}
+
+pub async fn blame_file_async(
+    repo_path: PathBuf,
+    file_path: String,
+    rev: Option<String>,
+) -> Result<Vec<LineBlame>, CoreError> {
+    tokio::task::spawn_blocking(move || git::blame_file(&repo_path, &file_path, rev.as_deref()))
+        .await?
+}
+
+pub async fn get_log_async(repo_path: PathBuf, options: LogOptions) -> Result<LogPage, CoreError> {
+    tokio::task::spawn_blocking(move || history::get_log(&repo_path, &options)).await?
+}
+
+pub async fn get_file_history_async(
+    repo_path: PathBuf,
+    file_path: String,
+    options: LogOptions,
+) -> Result<FileHistoryPage, CoreError> {
+    tokio::task::spawn_blocking(move || history::get_file_history(&repo_path, &file_path, &options))
         .await?
 }
 
+pub async fn stage_file_async(
+    repo_path: PathBuf,
+    file_path: String,
+) -> Result<GitStatus, CoreError> {
+    tokio::task::spawn_blocking(move || staging::stage_file(&repo_path, &file_path)).await?
+}
+
+pub async fn unstage_file_async(
+    repo_path: PathBuf,
+    file_path: String,
+) -> Result<GitStatus, CoreError> {
+    tokio::task::spawn_blocking(move || staging::unstage_file(&repo_path, &file_path)).await?
+}
+
+pub async fn apply_selection_async(
+    repo_path: PathBuf,
+    file_path: String,
+    action: PatchAction,
+    selections: Vec<HunkSelection>,
+    options: DiffOptions,
+) -> Result<GitStatus, CoreError> {
+    tokio::task::spawn_blocking(move || {
+        staging::apply_selection(&repo_path, &file_path, action, &selections, &options)
+    })
+    .await?
+}
+
+pub async fn create_commit_async(
+    repo_path: PathBuf,
+    message: String,
+    amend: bool,
+) -> Result<CommitInfo, CoreError> {
+    tokio::task::spawn_blocking(move || commit::create_commit(&repo_path, &message, amend)).await?
+}
+
+pub async fn discard_file_async(
+    repo_path: PathBuf,
+    file_path: String,
+) -> Result<DiscardRecord, CoreError> {
+    tokio::task::spawn_blocking(move || discard::discard_file(&repo_path, &file_path)).await?
+}
+
+pub async fn list_discards_async(repo_path: PathBuf) -> Result<Vec<DiscardRecord>, CoreError> {
+    tokio::task::spawn_blocking(move || discard::list_discards(&repo_path)).await?
+}
+
+pub async fn restore_discard_async(
+    repo_path: PathBuf,
+    discard_id: String,
+) -> Result<GitStatus, CoreError> {
+    tokio::task::spawn_blocking(move || discard::restore_discard(&repo_path, &discard_id)).await?
+}
+
+pub async fn get_conflict_contents_async(
+    repo_path: PathBuf,
+    file_path: String,
+) -> Result<ConflictContents, CoreError> {
+    tokio::task::spawn_blocking(move || conflict::get_conflict_contents(&repo_path, &file_path))
+        .await?
+}
+
+pub async fn resolve_conflicts_async(
+    repo_path: PathBuf,
+    file_path: String,
+    resolutions: Vec<ConflictResolution>,
+) -> Result<GitStatus, CoreError> {
+    tokio::task::spawn_blocking(move || {
+        conflict::resolve_conflicts(&repo_path, &file_path, &resolutions)
+    })
+    .await?
+}
+
+pub async fn mark_resolved_async(
+    repo_path: PathBuf,
+    file_path: String,
+) -> Result<GitStatus, CoreError> {
+    tokio::task::spawn_blocking(move || conflict::mark_resolved(&repo_path, &file_path)).await?
+}
+
+pub async fn list_stashes_async(repo_path: PathBuf) -> Result<Vec<StashEntry>, CoreError> {
+    tokio::task::spawn_blocking(move || stash::list_stashes(&repo_path)).await?
+}
+
+pub async fn create_stash_async(
+    repo_path: PathBuf,
+    message: Option<String>,
+    include_untracked: bool,
+) -> Result<StashEntry, CoreError> {
+    tokio::task::spawn_blocking(move || {
+        stash::create_stash(&repo_path, message.as_deref(), include_untracked)
+    })
+    .await?
+}
+
+pub async fn apply_stash_async(repo_path: PathBuf, index: u32) -> Result<GitStatus, CoreError> {
+    tokio::task::spawn_blocking(move || stash::apply_stash(&repo_path, index)).await?
+}
+
+pub async fn pop_stash_async(repo_path: PathBuf, index: u32) -> Result<GitStatus, CoreError> {
+    tokio::task::spawn_blocking(move || stash::pop_stash(&repo_path, index)).await?
+}
+
+pub async fn drop_stash_async(
+    repo_path: PathBuf,
+    index: u32,
+) -> Result<Vec<StashEntry>, CoreError> {
+    tokio::task::spawn_blocking(move || stash::drop_stash(&repo_path, index)).await?
+}
+
+pub async fn get_submodule_diff_async(
+    repo_path: PathBuf,
+    submodule_path: String,
+    target: DiffTarget,
+) -> Result<SubmoduleDiff, CoreError> {
+    tokio::task::spawn_blocking(move || {
+        submodule::get_submodule_diff(&repo_path, &submodule_path, &target)
+    })
+    .await?
+}
+
+pub async fn list_worktrees_async(repo_path: PathBuf) -> Result<Vec<WorktreeInfo>, CoreError> {
+    tokio::task::spawn_blocking(move || worktree::list_worktrees(&repo_path)).await?
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use git2::Repository;
+    use crate::test_support::create_test_repo;
     use std::fs;
-    use tempfile::TempDir;
-
-    fn create_test_repo() -> (TempDir, Repository) {
-        let temp_dir = TempDir::new().unwrap();
-        let repo = Repository::init(temp_dir.path()).unwrap();
-
-        let mut config = repo.config().unwrap();
-        config.set_str("user.name", "Test User").unwrap();
-        config.set_str("user.email", "test@example.com").unwrap();
-
-        (temp_dir, repo)
-    }
 
     #[tokio::test]
     async fn test_get_status_async() {
@@ -0,0 +1,383 @@
+use crate::error::CoreError;
+use crate::git::{delta_to_entry, diff_similarity, discover_repository, find_similar};
+use crate::types::{
+    CommitInfo, CommitSignature, DiffOptions, DiffTarget, FileEntry, FileEntryKind,
+    FileHistoryEntry, FileHistoryPage, LogOptions, LogPage,
+};
+use git2::{Commit, Delta, Oid, Repository, Revwalk, Sort};
+use std::collections::HashMap;
+use std::path::Path;
+
+pub(crate) fn signature_info(signature: &git2::Signature) -> CommitSignature {
+    CommitSignature {
+        name: String::from_utf8_lossy(signature.name_bytes()).into_owned(),
+        email: String::from_utf8_lossy(signature.email_bytes()).into_owned(),
+        time: signature.when().seconds(),
+        offset_minutes: signature.when().offset_minutes(),
+    }
+}
+
+pub(crate) fn commit_info(commit: &Commit) -> CommitInfo {
+    let id = commit.id().to_string();
+    CommitInfo {
+        short_id: id.chars().take(7).collect(),
+        id,
+        summary: String::from_utf8_lossy(commit.summary_bytes().unwrap_or_default()).into_owned(),
+        body: commit
+            .body_bytes()
+            .map(|b| String::from_utf8_lossy(b).into_owned()),
+        author: signature_info(&commit.author()),
+        committer: signature_info(&commit.committer()),
+        parents: commit.parent_ids().map(|p| p.to_string()).collect(),
+    }
+}
+
+fn path_entry_id(commit: &Commit, path: &Path) -> Option<Oid> {
+    commit.tree().ok()?.get_path(path).ok().map(|e| e.id())
+}
+
+fn touches_path(commit: &Commit, path: &Path) -> bool {
+    let own = path_entry_id(commit, path);
+    if commit.parent_count() == 0 {
+        return own.is_some();
+    }
+    commit
+        .parents()
+        .all(|parent| path_entry_id(&parent, path) != own)
+}
+
+fn matches_author(commit: &Commit, needle: &str) -> bool {
+    let author = commit.author();
+    let needle = needle.to_lowercase();
+    [author.name_bytes(), author.email_bytes()]
+        .iter()
+        .any(|field| {
+            String::from_utf8_lossy(field)
+                .to_lowercase()
+                .contains(&needle)
+        })
+}
+
+fn revwalk_for<'r>(
+    repo: &'r Repository,
+    options: &LogOptions,
+) -> Result<Option<Revwalk<'r>>, CoreError> {
+    let start = options.start.as_deref().unwrap_or("HEAD");
+    if start == "HEAD" && repo.head().is_err() {
+        return Ok(None);
+    }
+
+    let mut revwalk = repo.revwalk()?;
+    revwalk.set_sorting(Sort::TOPOLOGICAL | Sort::TIME)?;
+    revwalk.push(repo.revparse_single(start)?.peel_to_commit()?.id())?;
+    if options.first_parent {
+        revwalk.simplify_first_parent()?;
+    }
+    Ok(Some(revwalk))
+}
+
+pub fn get_log(repo_path: &Path, options: &LogOptions) -> Result<LogPage, CoreError> {
+    let repo = discover_repository(repo_path)?;
+    get_log_with_repo(&repo, options)
+}
+
+pub(crate) fn get_log_with_repo(
+    repo: &Repository,
+    options: &LogOptions,
+) -> Result<LogPage, CoreError> {
+    let Some(revwalk) = revwalk_for(repo, options)? else {
+        return Ok(LogPage {
+            commits: Vec::new(),
+            has_more: false,
+        });
+    };
+
+    let path = options.path.as_deref().map(Path::new);
+    let limit = options.limit as usize;
+    let mut skipped = 0;
+    let mut commits = Vec::new();
+    let mut has_more = false;
+
+    for oid in revwalk {
+        let commit = repo.find_commit(oid?)?;
+        if path.is_some_and(|p| !touches_path(&commit, p)) {
+            continue;
+        }
+        if options
+            .author
+            .as_deref()
+            .is_some_and(|a| !matches_author(&commit, a))
+        {
+            continue;
+        }
+        if skipped < options.offset {
+            skipped += 1;
+            continue;
+        }
+        if commits.len() == limit {
+            has_more = true;
+            break;
+        }
+        commits.push(commit_info(&commit));
+    }
+
+    Ok(LogPage { commits, has_more })
+}
+
+/// How `commit` changed `path` relative to its first parent, with renames detected.
+fn path_change(
+    repo: &Repository,
+    commit: &Commit,
+    path: &str,
+) -> Result<Option<FileEntry>, CoreError> {
+    let parent_tree = commit.parents().next().map(|p| p.tree()).transpose()?;
+    let tree = commit.tree()?;
+
+    let mut opts = git2::DiffOptions::new();
+    opts.pathspec(path).disable_pathspec_match(true);
+    let diff = repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&tree), Some(&mut opts))?;
+    let Some(delta) = diff.deltas().next() else {
+        return Ok(None);
+    };
+
+    if delta.status() == Delta::Added && parent_tree.is_some() {
+        let mut full = repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&tree), None)?;
+        find_similar(&mut full, &DiffOptions::default())?;
+        let renamed = full
+            .deltas()
+            .find(|d| d.status() == Delta::Renamed && d.new_file().path() == Some(Path::new(path)));
+        if let Some(renamed) = renamed {
+            return Ok(delta_to_entry(repo, &renamed, &diff_similarity(&full)?));
+        }
+    }
+
+    Ok(delta_to_entry(repo, &delta, &HashMap::new()))
+}
+
+/// Commits that touched `file_path`, following renames like `git log --follow`.
+/// `options.path` is ignored; the remaining options behave as in `get_log`.
+pub fn get_file_history(
+    repo_path: &Path,
+    file_path: &str,
+    options: &LogOptions,
+) -> Result<FileHistoryPage, CoreError> {
+    let repo = discover_repository(repo_path)?;
+    get_file_history_with_repo(&repo, file_path, options)
+}
+
+pub(crate) fn get_file_history_with_repo(
+    repo: &Repository,
+    file_path: &str,
+    options: &LogOptions,
+) -> Result<FileHistoryPage, CoreError> {
+    let Some(revwalk) = revwalk_for(repo, options)? else {
+        return Ok(FileHistoryPage {
+            entries: Vec::new(),
+            has_more: false,
+        });
+    };
+
+    let mut current = file_path.to_owned();
+    let limit = options.limit as usize;
+    let mut skipped = 0;
+    let mut entries = Vec::new();
+    let mut has_more = false;
+
+    for oid in revwalk {
+        let commit = repo.find_commit(oid?)?;
+        if !touches_path(&commit, Path::new(&current)) {
+            continue;
+        }
+        let Some(file) = path_change(repo, &commit, &current)? else {
+            continue;
+        };
+        let renamed_from = match &file.kind {
+            FileEntryKind::Renamed { old_path, .. } => Some(old_path.clone()),
+            _ => None,
+        };
+
+        let included = options
+            .author
+            .as_deref()
+            .is_none_or(|a| matches_author(&commit, a));
+        if included {
+            if skipped < options.offset {
+                skipped += 1;
+            } else if entries.len() == limit {
+                has_more = true;
+                break;
+            } else {
+                entries.push(FileHistoryEntry {
+                    commit: commit_info(&commit),
+                    file,
+                    target: DiffTarget::Range {
+                        from: None,
+                        to: commit.id().to_string(),
+                    },
+                });
+            }
+        }
+
+        if let Some(old_path) = renamed_from {
+            current = old_path;
+        }
+    }
+
+    Ok(FileHistoryPage { entries, has_more })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::create_test_repo;
+    use git2::Signature;
+    use std::fs;
+
+    fn commit_as(repo: &Repository, author: &str, path: &str, content: &str, message: &str) {
+        let repo_path = repo.workdir().unwrap();
+        fs::write(repo_path.join(path), content).unwrap();
+
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new(path)).unwrap();
+        index.write().unwrap();
+
+        let tree_id = index.write_tree().unwrap();
+        let tree = repo.find_tree(tree_id).unwrap();
+        let sig =
+            Signature::now(author, &format!("{}@example.com", author.to_lowercase())).unwrap();
+
+        let parent = repo.head().ok().and_then(|h| h.peel_to_commit().ok());
+        let parents: Vec<&git2::Commit> = parent.iter().collect();
+
+        repo.commit(Some("HEAD"), &sig, &sig, message, &tree, &parents)
+            .unwrap();
+    }
+
+    fn rename_file(repo: &Repository, from: &str, to: &str, content: &str, message: &str) {
+        let repo_path = repo.workdir().unwrap();
+        fs::remove_file(repo_path.join(from)).unwrap();
+
+        let mut index = repo.index().unwrap();
+        index.remove_path(Path::new(from)).unwrap();
+        index.write().unwrap();
+
+        commit_as(repo, "Alice", to, content, message);
+    }
+
+    #[test]
+    fn test_get_log_empty_repo() {
+        let (temp_dir, _repo) = create_test_repo();
+
+        let page = get_log(temp_dir.path(), &LogOptions::default()).unwrap();
+        assert!(page.commits.is_empty());
+        assert!(!page.has_more);
+    }
+
+    #[test]
+    fn test_get_log_paginates_newest_first() {
+        let (temp_dir, repo) = create_test_repo();
+
+        for i in 1..=5 {
+            commit_as(
+                &repo,
+                "Alice",
+                "file.txt",
+                &i.to_string(),
+                &format!("Commit {i}\n\nBody {i}"),
+            );
+        }
+
+        let options = LogOptions {
+            offset: 1,
+            limit: 2,
+            ..LogOptions::default()
+        };
+        let page = get_log(temp_dir.path(), &options).unwrap();
+
+        assert_eq!(page.commits.len(), 2);
+        assert!(page.has_more);
+        assert_eq!(page.commits[0].summary, "Commit 4");
+        assert_eq!(page.commits[0].body.as_deref(), Some("Body 4"));
+        assert_eq!(page.commits[0].author.name, "Alice");
+        assert_eq!(page.commits[0].parents, vec![page.commits[1].id.clone()]);
+    }
+
+    #[test]
+    fn test_get_log_filters_by_path_and_author() {
+        let (temp_dir, repo) = create_test_repo();
+
+        commit_as(&repo, "Alice", "a.txt", "1", "Alice a");
+        commit_as(&repo, "Bob", "b.txt", "1", "Bob b");
+        commit_as(&repo, "Bob", "a.txt", "2", "Bob a");
+
+        let by_path = get_log(
+            temp_dir.path(),
+            &LogOptions {
+                path: Some("a.txt".to_string()),
+                ..LogOptions::default()
+            },
+        )
+        .unwrap();
+        let summaries: Vec<&str> = by_path.commits.iter().map(|c| c.summary.as_str()).collect();
+        assert_eq!(summaries, vec!["Bob a", "Alice a"]);
+
+        let by_author = get_log(
+            temp_dir.path(),
+            &LogOptions {
+                author: Some("bob".to_string()),
+                ..LogOptions::default()
+            },
+        )
+        .unwrap();
+        let summaries: Vec<&str> = by_author
+            .commits
+            .iter()
+            .map(|c| c.summary.as_str())
+            .collect();
+        assert_eq!(summaries, vec!["Bob a", "Bob b"]);
+    }
+
+    #[test]
+    fn test_get_file_history_follows_renames() {
+        let (temp_dir, repo) = create_test_repo();
+        let content = "line 1\nline 2\nline 3\nline 4\nline 5\n";
+
+        commit_as(&repo, "Alice", "old.txt", content, "Add old");
+        commit_as(&repo, "Alice", "other.txt", "x", "Unrelated");
+        rename_file(&repo, "old.txt", "new.txt", content, "Rename");
+        commit_as(
+            &repo,
+            "Bob",
+            "new.txt",
+            "line 1\nline 2\nline 3\nline 4\nline 5 changed\n",
+            "Edit new",
+        );
+
+        let history = get_file_history(temp_dir.path(), "new.txt", &LogOptions::default()).unwrap();
+        let summaries: Vec<&str> = history
+            .entries
+            .iter()
+            .map(|e| e.commit.summary.as_str())
+            .collect();
+        assert_eq!(summaries, vec!["Edit new", "Rename", "Add old"]);
+
+        assert_eq!(history.entries[0].file.kind, FileEntryKind::Modified);
+        assert_eq!(history.entries[1].file.path, "new.txt");
+        assert!(matches!(
+            &history.entries[1].file.kind,
+            FileEntryKind::Renamed { old_path, similarity: 100 } if old_path == "old.txt"
+        ));
+        assert_eq!(history.entries[2].file.path, "old.txt");
+        assert_eq!(history.entries[2].file.kind, FileEntryKind::Added);
+
+        let oldest = &history.entries[2];
+        let diff = crate::git::get_file_diff(
+            temp_dir.path(),
+            &oldest.file.path,
+            &oldest.target,
+            &DiffOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(diff.path, "old.txt");
+    }
+}
@@ -0,0 +1,259 @@
+use crate::types::{DiffHunk, InlineChange, LineChangeType};
+
+const MAX_TOKEN_PRODUCT: usize = 40_000;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum TokenClass {
+    Word,
+    Space,
+    Other,
+}
+
+fn token_class(c: char) -> TokenClass {
+    if c.is_alphanumeric() || c == '_' {
+        TokenClass::Word
+    } else if c.is_whitespace() {
+        TokenClass::Space
+    } else {
+        TokenClass::Other
+    }
+}
+
+struct Token<'a> {
+    text: &'a str,
+    start: usize,
+    end: usize,
+}
+
+/// Splits `line` into tokens whose offsets count UTF-16 code units, matching JavaScript strings.
+fn tokenize(line: &str) -> Vec<Token<'_>> {
+    let mut tokens: Vec<Token<'_>> = Vec::new();
+    let mut current: Option<(TokenClass, usize, usize)> = None;
+    let mut unit_idx = 0;
+
+    for (byte_idx, c) in line.char_indices() {
+        let class = token_class(c);
+        match current {
+            Some((prev, _, _)) if prev == class && class != TokenClass::Other => {}
+            Some((_, byte_start, unit_start)) => {
+                tokens.push(Token {
+                    text: &line[byte_start..byte_idx],
+                    start: unit_start,
+                    end: unit_idx,
+                });
+                current = Some((class, byte_idx, unit_idx));
+            }
+            None => current = Some((class, byte_idx, unit_idx)),
+        }
+        unit_idx += c.len_utf16();
+    }
+
+    if let Some((_, byte_start, unit_start)) = current {
+        tokens.push(Token {
+            text: &line[byte_start..],
+            start: unit_start,
+            end: unit_idx,
+        });
+    }
+
+    tokens
+}
+
+fn unmatched_tokens(old: &[Token<'_>], new: &[Token<'_>]) -> Option<(Vec<bool>, Vec<bool>)> {
+    if old.len().saturating_mul(new.len()) > MAX_TOKEN_PRODUCT {
+        return None;
+    }
+
+    let width = new.len() + 1;
+    let mut lcs = vec![0u32; (old.len() + 1) * width];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i * width + j] = if old[i].text == new[j].text {
+                lcs[(i + 1) * width + j + 1] + 1
+            } else {
+                lcs[(i + 1) * width + j].max(lcs[i * width + j + 1])
+            };
+        }
+    }
+
+    let mut old_changed = vec![true; old.len()];
+    let mut new_changed = vec![true; new.len()];
+    let mut has_common_word = false;
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() && j < new.len() {
+        if old[i].text == new[j].text {
+            old_changed[i] = false;
+            new_changed[j] = false;
+            has_common_word |= !old[i].text.trim().is_empty();
+            i += 1;
+            j += 1;
+        } else if lcs[(i + 1) * width + j] >= lcs[i * width + j + 1] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+
+    has_common_word.then_some((old_changed, new_changed))
+}
+
+fn changed_ranges(tokens: &[Token<'_>], changed: &[bool]) -> Vec<InlineChange> {
+    let mut ranges: Vec<InlineChange> = Vec::new();
+    for (token, _) in tokens.iter().zip(changed).filter(|(_, c)| **c) {
+        let start = u32::try_from(token.start).unwrap_or(u32::MAX);
+        let end = u32::try_from(token.end).unwrap_or(u32::MAX);
+        match ranges.last_mut() {
+            Some(last) if last.end == start => last.end = end,
+            _ => ranges.push(InlineChange { start, end }),
+        }
+    }
+    ranges
+}
+
+fn annotate_pair(hunk: &mut DiffHunk, old_idx: usize, new_idx: usize) {
+    let old_tokens = tokenize(&hunk.lines[old_idx].content);
+    let new_tokens = tokenize(&hunk.lines[new_idx].content);
+
+    let Some((old_changed, new_changed)) = unmatched_tokens(&old_tokens, &new_tokens) else {
+        return;
+    };
+    let old_ranges = changed_ranges(&old_tokens, &old_changed);
+    let new_ranges = changed_ranges(&new_tokens, &new_changed);
+
+    hunk.lines[old_idx].inline_changes = old_ranges;
+    hunk.lines[new_idx].inline_changes = new_ranges;
+}
+
+pub(crate) fn annotate_hunk(hunk: &mut DiffHunk) {
+    let mut idx = 0;
+    while idx < hunk.lines.len() {
+        let deletions_start = idx;
+        while idx < hunk.lines.len() && hunk.lines[idx].change_type == LineChangeType::Deletion {
+            idx += 1;
+        }
+        let additions_start = idx;
+        while idx < hunk.lines.len() && hunk.lines[idx].change_type == LineChangeType::Addition {
+            idx += 1;
+        }
+
+        let deletions = additions_start - deletions_start;
+        let additions = idx - additions_start;
+        for offset in 0..deletions.min(additions) {
+            annotate_pair(hunk, deletions_start + offset, additions_start + offset);
+        }
+
+        if idx == deletions_start {
+            idx += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::DiffLine;
+
+    fn line(change_type: LineChangeType, content: &str) -> DiffLine {
+        DiffLine {
+            change_type,
+            content: content.to_string(),
+            old_line_no: None,
+            new_line_no: None,
+            inline_changes: Vec::new(),
+            blame: None,
+        }
+    }
+
+    fn hunk(lines: Vec<DiffLine>) -> DiffHunk {
+        DiffHunk {
+            old_start: 1,
+            old_lines: 0,
+            new_start: 1,
+            new_lines: 0,
+            header: String::new(),
+            lines,
+        }
+    }
+
+    #[test]
+    fn test_annotate_renamed_identifier() {
+        let mut h = hunk(vec![
+            line(LineChangeType::Deletion, "let total = compute(items);"),
+            line(LineChangeType::Addition, "let sum = compute(items);"),
+        ]);
+
+        annotate_hunk(&mut h);
+
+        assert_eq!(
+            h.lines[0].inline_changes,
+            vec![InlineChange { start: 4, end: 9 }]
+        );
+        assert_eq!(
+            h.lines[1].inline_changes,
+            vec![InlineChange { start: 4, end: 7 }]
+        );
+    }
+
+    #[test]
+    fn test_annotate_pairs_only_matching_count() {
+        let mut h = hunk(vec![
+            line(LineChangeType::Context, "fn main() {"),
+            line(LineChangeType::Deletion, "    call(a);"),
+            line(LineChangeType::Addition, "    call(b);"),
+            line(LineChangeType::Addition, "    other();"),
+        ]);
+
+        annotate_hunk(&mut h);
+
+        assert!(h.lines[0].inline_changes.is_empty());
+        assert_eq!(
+            h.lines[1].inline_changes,
+            vec![InlineChange { start: 9, end: 10 }]
+        );
+        assert_eq!(
+            h.lines[2].inline_changes,
+            vec![InlineChange { start: 9, end: 10 }]
+        );
+        assert!(h.lines[3].inline_changes.is_empty());
+    }
+
+    #[test]
+    fn test_annotate_skips_unrelated_lines() {
+        let mut h = hunk(vec![
+            line(LineChangeType::Deletion, "alpha beta"),
+            line(LineChangeType::Addition, "gamma delta"),
+        ]);
+
+        annotate_hunk(&mut h);
+
+        assert!(h.lines[0].inline_changes.is_empty());
+        assert!(h.lines[1].inline_changes.is_empty());
+    }
+
+    #[test]
+    fn test_annotate_uses_utf16_offsets() {
+        let mut h = hunk(vec![
+            line(LineChangeType::Deletion, "const é = \"café\";"),
+            line(LineChangeType::Addition, "const é = \"thé\";"),
+        ]);
+
+        annotate_hunk(&mut h);
+
+        assert_eq!(
+            h.lines[1].inline_changes,
+            vec![InlineChange { start: 11, end: 14 }]
+        );
+
+        let mut astral = hunk(vec![
+            line(LineChangeType::Deletion, "let 🎉 = old;"),
+            line(LineChangeType::Addition, "let 🎉 = new;"),
+        ]);
+
+        annotate_hunk(&mut astral);
+
+        assert_eq!(
+            astral.lines[1].inline_changes,
+            vec![InlineChange { start: 9, end: 12 }]
+        );
+    }
+}
@@ -1,20 +1,55 @@
 pub mod comments;
+pub mod commit;
+pub mod conflict;
+pub mod discard;
 pub mod error;
 pub mod fs;
 pub mod git;
 pub mod git_async;
+pub mod history;
+mod inline_diff;
+pub mod session;
+pub mod staging;
+pub mod stash;
+pub mod submodule;
+#[cfg(test)]
+mod test_support;
 pub mod types;
+pub mod worktree;
 
 pub use comments::{
     delete_comment, get_comments_for_file, load_comments, re_anchor_comment, save_comment,
 };
+pub use commit::create_commit;
+pub use conflict::{get_conflict_contents, mark_resolved, resolve_conflicts};
+pub use discard::{discard_file, list_discards, restore_discard};
 pub use error::CoreError;
 pub use fs::{extension_to_lang, read_file};
 pub use git::{
-    discover_repository, get_file_diff, get_git_file_contents, get_status, open_repository,
+    blame_file, discover_repository, get_changed_files, get_changeset_diff, get_context_lines,
+    get_file_diff, get_git_file_contents, get_status, get_status_with_stats, open_repository,
 };
-pub use git_async::{get_file_diff_async, get_git_file_contents_async, get_status_async};
+pub use git_async::{
+    apply_selection_async, apply_stash_async, blame_file_async, create_commit_async,
+    create_stash_async, discard_file_async, drop_stash_async, get_changed_files_async,
+    get_changeset_diff_async, get_conflict_contents_async, get_context_lines_async,
+    get_file_diff_async, get_file_history_async, get_git_file_contents_async, get_log_async,
+    get_status_async, get_status_with_stats_async, get_submodule_diff_async, list_discards_async,
+    list_stashes_async, mark_resolved_async, pop_stash_async, resolve_conflicts_async,
+    restore_discard_async, stage_file_async, unstage_file_async,
+};
+pub use history::{get_file_history, get_log};
+pub use session::RepoSession;
+pub use staging::{apply_selection, stage_file, unstage_file};
+pub use stash::{apply_stash, create_stash, drop_stash, list_stashes, pop_stash};
+pub use submodule::get_submodule_diff;
 pub use types::{
-    Comment, CommentCollection, DiffFile, DiffHunk, DiffLine, DiffTarget, FileContent, FileDiff,
-    FileEntry, FileEntryKind, GitFileContents, GitStatus, LineChangeType, ReadFileResult,
+    ChangeSummary, ChangesetDiff, ChangesetFile, Comment, CommentCollection, CommitInfo,
+    CommitSignature, ConflictContents, ConflictRegion, ConflictResolution, DiffAlgorithm, DiffFile,
+    DiffHunk, DiffLine, DiffOptions, DiffSide, DiffStats, DiffTarget, DiscardRecord, FileContent,
+    FileDiff, FileEntry, FileEntryKind, FileHistoryEntry, FileHistoryPage, GitFileContents,
+    GitStatus, HeadState, HunkSelection, InlineChange, LineBlame, LineChangeType, LogOptions,
+    LogPage, PatchAction, ReadFileResult, RenameDetection, RepoOperation, StashEntry,
+    SubmoduleDiff, UpstreamStatus, WhitespaceMode, WorktreeInfo,
 };
+pub use worktree::{list_worktrees, worktree_root};
@@ -0,0 +1,445 @@
+use crate::commit::create_commit_with_repo;
+use crate::conflict::{get_conflict_contents_with_repo, resolve_conflicts_with_repo};
+use crate::discard::{discard_file_with_repo, list_discards_with_repo, restore_discard_with_repo};
+use crate::error::CoreError;
+use crate::git::{
+    Snapshot, add_status_stats, blame_file_with_repo, discover_repository,
+    get_changed_files_with_repo, get_changeset_diff_with_repo, get_context_lines_with_repo,
+    get_file_diff_with_repo, get_git_file_contents_with_repo, get_status_with_repo,
+};
+use crate::history::{get_file_history_with_repo, get_log_with_repo};
+use crate::staging::{apply_selection_with_repo, stage_file_with_repo, unstage_file_with_repo};
+use crate::stash::{
+    apply_stash_with_repo, create_stash_with_repo, drop_stash_with_repo, list_stashes_with_repo,
+};
+use crate::submodule::get_submodule_diff_with_repo;
+use crate::types::{
+    ChangesetDiff, CommitInfo, ConflictContents, ConflictResolution, DiffLine, DiffOptions,
+    DiffSide, DiffTarget, DiscardRecord, FileDiff, FileEntry, FileHistoryPage, GitFileContents,
+    GitStatus, HunkSelection, LineBlame, LogOptions, LogPage, PatchAction, StashEntry,
+    SubmoduleDiff, WorktreeInfo,
+};
+use crate::worktree::list_worktrees_with_repo;
+use git2::{Oid, Repository};
+use std::collections::{HashMap, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+const BLOB_CACHE_BYTES: usize = 64 * 1024 * 1024;
+
+/// Blob contents keyed by id, evicting the oldest entries once `capacity` bytes are held.
+pub(crate) struct BlobCache {
+    capacity: usize,
+    size: usize,
+    entries: HashMap<Oid, Arc<[u8]>>,
+    order: VecDeque<Oid>,
+}
+
+impl BlobCache {
+    pub(crate) fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            size: 0,
+            entries: HashMap::new(),
+            order: VecDeque::new(),
+        }
+    }
+
+    /// A cache that never retains anything, for one-shot calls.
+    pub(crate) fn disabled() -> Self {
+        Self::new(0)
+    }
+
+    pub(crate) fn get(&mut self, repo: &Repository, id: Oid) -> Result<Arc<[u8]>, CoreError> {
+        if let Some(bytes) = self.entries.get(&id) {
+            return Ok(Arc::clone(bytes));
+        }
+
+        let bytes: Arc<[u8]> = Arc::from(repo.find_blob(id)?.content());
+        if bytes.len() <= self.capacity {
+            self.size += bytes.len();
+            while self.size > self.capacity {
+                let Some(oldest) = self.order.pop_front() else {
+                    break;
+                };
+                if let Some(evicted) = self.entries.remove(&oldest) {
+                    self.size -= evicted.len();
+                }
+            }
+            self.order.push_back(id);
+            self.entries.insert(id, Arc::clone(&bytes));
+        }
+        Ok(bytes)
+    }
+}
+
+/// Reloads the index; libgit2 skips the read when the file on disk hasn't changed.
+fn refresh_index(repo: &Repository) -> Result<(), CoreError> {
+    if repo.is_bare() {
+        return Ok(());
+    }
+    repo.index()?.read(false)?;
+    Ok(())
+}
+
+/// What a session last read: the commit HEAD pointed at and its tree.
+#[derive(Default)]
+struct SnapshotCache {
+    head: Option<(Oid, Oid)>,
+}
+
+impl SnapshotCache {
+    /// Re-reads the index if it changed on disk, and reuses HEAD's tree while HEAD stays on the
+    /// same commit.
+    fn snapshot<'r>(&mut self, repo: &'r Repository) -> Result<Snapshot<'r>, CoreError> {
+        refresh_index(repo)?;
+        let Ok(head) = repo.refname_to_id("HEAD") else {
+            self.head = None;
+            return Ok(Snapshot::with_head_tree(repo, None));
+        };
+        let tree_id = match self.head {
+            Some((commit, tree)) if commit == head => tree,
+            _ => {
+                let tree = repo.find_commit(head)?.tree_id();
+                self.head = Some((head, tree));
+                tree
+            }
+        };
+        Ok(Snapshot::with_head_tree(
+            repo,
+            Some(repo.find_tree(tree_id)?),
+        ))
+    }
+}
+
+/// A long-lived handle to one repository. Blob contents are cached by id, the index is re-read
+/// only when it has changed on disk, and HEAD's tree is kept until HEAD moves.
+pub struct RepoSession {
+    path: PathBuf,
+    repo: Repository,
+    blobs: BlobCache,
+    cache: SnapshotCache,
+}
+
+impl RepoSession {
+    pub fn open(repo_path: &Path) -> Result<Self, CoreError> {
+        Ok(Self {
+            path: repo_path.to_path_buf(),
+            repo: discover_repository(repo_path)?,
+            blobs: BlobCache::new(BLOB_CACHE_BYTES),
+            cache: SnapshotCache::default(),
+        })
+    }
+
+    /// The path the session was opened with.
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    #[must_use]
+    pub fn repository(&self) -> &Repository {
+        &self.repo
+    }
+
+    pub fn status(&mut self) -> Result<GitStatus, CoreError> {
+        get_status_with_repo(&self.cache.snapshot(&self.repo)?)
+    }
+
+    pub fn status_with_stats(&mut self) -> Result<GitStatus, CoreError> {
+        let snapshot = self.cache.snapshot(&self.repo)?;
+        let mut status = get_status_with_repo(&snapshot)?;
+        add_status_stats(&snapshot, &mut status)?;
+        Ok(status)
+    }
+
+    pub fn changed_files(
+        &mut self,
+        target: &DiffTarget,
+        options: &DiffOptions,
+    ) -> Result<Vec<FileEntry>, CoreError> {
+        get_changed_files_with_repo(&self.cache.snapshot(&self.repo)?, target, options)
+    }
+
+    pub fn file_diff(
+        &mut self,
+        file_path: &str,
+        target: &DiffTarget,
+        options: &DiffOptions,
+    ) -> Result<FileDiff, CoreError> {
+        get_file_diff_with_repo(
+            &self.cache.snapshot(&self.repo)?,
+            file_path,
+            target,
+            options,
+        )
+    }
+
+    pub fn changeset_diff(
+        &mut self,
+        target: &DiffTarget,
+        options: &DiffOptions,
+        max_lines: Option<u32>,
+    ) -> Result<ChangesetDiff, CoreError> {
+        get_changeset_diff_with_repo(
+            &self.cache.snapshot(&self.repo)?,
+            target,
+            options,
+            max_lines,
+        )
+    }
+
+    pub fn git_file_contents(
+        &mut self,
+        file_path: &str,
+        target: &DiffTarget,
+    ) -> Result<GitFileContents, CoreError> {
+        let snapshot = self.cache.snapshot(&self.repo)?;
+        get_git_file_contents_with_repo(&snapshot, &mut self.blobs, file_path, target)
+    }
+
+    pub fn context_lines(
+        &mut self,
+        file_path: &str,
+        target: &DiffTarget,
+        side: DiffSide,
+        start: u32,
+        end: u32,
+        options: &DiffOptions,
+    ) -> Result<Vec<DiffLine>, CoreError> {
+        let snapshot = self.cache.snapshot(&self.repo)?;
+        get_context_lines_with_repo(
+            &snapshot,
+            &mut self.blobs,
+            file_path,
+            target,
+            side,
+            start,
+            end,
+            options,
+        )
+    }
+
+    pub fn create_commit(&mut self, message: &str, amend: bool) -> Result<CommitInfo, CoreError> {
+        refresh_index(&self.repo)?;
+        create_commit_with_repo(&self.repo, message, amend)
+    }
+
+    pub fn stage_file(&mut self, file_path: &str) -> Result<GitStatus, CoreError> {
+        stage_file_with_repo(&self.cache.snapshot(&self.repo)?, file_path)
+    }
+
+    pub fn unstage_file(&mut self, file_path: &str) -> Result<GitStatus, CoreError> {
+        unstage_file_with_repo(&self.cache.snapshot(&self.repo)?, file_path)
+    }
+
+    pub fn apply_selection(
+        &mut self,
+        file_path: &str,
+        action: PatchAction,
+        selections: &[HunkSelection],
+        options: &DiffOptions,
+    ) -> Result<GitStatus, CoreError> {
+        apply_selection_with_repo(
+            &self.cache.snapshot(&self.repo)?,
+            file_path,
+            action,
+            selections,
+            options,
+        )
+    }
+
+    pub fn discard_file(&mut self, file_path: &str) -> Result<DiscardRecord, CoreError> {
+        refresh_index(&self.repo)?;
+        discard_file_with_repo(&self.repo, file_path)
+    }
+
+    pub fn list_discards(&mut self) -> Result<Vec<DiscardRecord>, CoreError> {
+        list_discards_with_repo(&self.repo)
+    }
+
+    pub fn restore_discard(&mut self, discard_id: &str) -> Result<GitStatus, CoreError> {
+        restore_discard_with_repo(&self.cache.snapshot(&self.repo)?, discard_id)
+    }
+
+    pub fn conflict_contents(&mut self, file_path: &str) -> Result<ConflictContents, CoreError> {
+        refresh_index(&self.repo)?;
+        get_conflict_contents_with_repo(&self.repo, file_path)
+    }
+
+    pub fn resolve_conflicts(
+        &mut self,
+        file_path: &str,
+        resolutions: &[ConflictResolution],
+    ) -> Result<GitStatus, CoreError> {
+        resolve_conflicts_with_repo(&self.cache.snapshot(&self.repo)?, file_path, resolutions)
+    }
+
+    pub fn mark_resolved(&mut self, file_path: &str) -> Result<GitStatus, CoreError> {
+        stage_file_with_repo(&self.cache.snapshot(&self.repo)?, file_path)
+    }
+
+    pub fn log(&mut self, options: &LogOptions) -> Result<LogPage, CoreError> {
+        get_log_with_repo(&self.repo, options)
+    }
+
+    pub fn file_history(
+        &mut self,
+        file_path: &str,
+        options: &LogOptions,
+    ) -> Result<FileHistoryPage, CoreError> {
+        get_file_history_with_repo(&self.repo, file_path, options)
+    }
+
+    pub fn blame(
+        &mut self,
+        file_path: &str,
+        rev: Option<&str>,
+    ) -> Result<Vec<LineBlame>, CoreError> {
+        blame_file_with_repo(&self.repo, file_path, rev)
+    }
+
+    pub fn list_stashes(&mut self) -> Result<Vec<StashEntry>, CoreError> {
+        list_stashes_with_repo(&mut self.repo)
+    }
+
+    pub fn create_stash(
+        &mut self,
+        message: Option<&str>,
+        include_untracked: bool,
+    ) -> Result<StashEntry, CoreError> {
+        refresh_index(&self.repo)?;
+        create_stash_with_repo(&mut self.repo, message, include_untracked)
+    }
+
+    pub fn apply_stash(&mut self, index: u32) -> Result<GitStatus, CoreError> {
+        refresh_index(&self.repo)?;
+        apply_stash_with_repo(&mut self.repo, index, false)
+    }
+
+    pub fn pop_stash(&mut self, index: u32) -> Result<GitStatus, CoreError> {
+        refresh_index(&self.repo)?;
+        apply_stash_with_repo(&mut self.repo, index, true)
+    }
+
+    pub fn drop_stash(&mut self, index: u32) -> Result<Vec<StashEntry>, CoreError> {
+        drop_stash_with_repo(&mut self.repo, index)
+    }
+
+    pub fn submodule_diff(
+        &mut self,
+        submodule_path: &str,
+        target: &DiffTarget,
+    ) -> Result<SubmoduleDiff, CoreError> {
+        get_submodule_diff_with_repo(&self.cache.snapshot(&self.repo)?, submodule_path, target)
+    }
+
+    pub fn worktrees(&mut self) -> Result<Vec<WorktreeInfo>, CoreError> {
+        list_worktrees_with_repo(&self.repo)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{commit_file, create_test_repo};
+    use crate::types::FileContent;
+    use std::fs;
+
+    #[test]
+    fn test_session_sees_external_index_changes() {
+        let (temp_dir, repo) = create_test_repo();
+        commit_file(&repo, "file.txt", "one\n", "Initial commit");
+
+        let mut session = RepoSession::open(temp_dir.path()).unwrap();
+        assert!(session.status().unwrap().staged.is_empty());
+
+        fs::write(temp_dir.path().join("file.txt"), "two\n").unwrap();
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new("file.txt")).unwrap();
+        index.write().unwrap();
+
+        let contents = session
+            .git_file_contents("file.txt", &DiffTarget::Staged)
+            .unwrap();
+        assert_eq!(
+            contents.new_file.content,
+            Some(FileContent::Text {
+                contents: "two\n".to_owned(),
+            })
+        );
+        assert_eq!(session.status().unwrap().staged.len(), 1);
+    }
+
+    #[test]
+    fn test_session_sees_same_size_index_rewrite() {
+        let (temp_dir, repo) = create_test_repo();
+        commit_file(&repo, "file.txt", "one\n", "Initial commit");
+        let mut session = RepoSession::open(temp_dir.path()).unwrap();
+
+        for contents in ["two\n", "six\n"] {
+            fs::write(temp_dir.path().join("file.txt"), contents).unwrap();
+            let mut index = repo.index().unwrap();
+            index.add_path(Path::new("file.txt")).unwrap();
+            index.write().unwrap();
+
+            let staged = session
+                .git_file_contents("file.txt", &DiffTarget::Staged)
+                .unwrap();
+            assert_eq!(
+                staged.new_file.content,
+                Some(FileContent::Text {
+                    contents: contents.to_owned(),
+                })
+            );
+        }
+    }
+
+    #[test]
+    fn test_session_follows_head_across_commits() {
+        let (temp_dir, repo) = create_test_repo();
+        commit_file(&repo, "file.txt", "one\n", "Initial commit");
+        let mut session = RepoSession::open(temp_dir.path()).unwrap();
+        let uncommitted = |session: &mut RepoSession| {
+            session
+                .changed_files(&DiffTarget::Uncommitted, &DiffOptions::default())
+                .unwrap()
+                .len()
+        };
+
+        fs::write(temp_dir.path().join("file.txt"), "two\n").unwrap();
+        assert_eq!(session.stage_file("file.txt").unwrap().staged.len(), 1);
+        session.create_commit("Second", false).unwrap();
+        assert!(session.status().unwrap().staged.is_empty());
+        assert_eq!(uncommitted(&mut session), 0);
+
+        commit_file(&repo, "file.txt", "three\n", "Third");
+        assert_eq!(uncommitted(&mut session), 0);
+        assert_eq!(
+            session.log(&LogOptions::default()).unwrap().commits.len(),
+            3
+        );
+    }
+
+    #[test]
+    fn test_blob_cache_evicts_oldest() {
+        let (_temp_dir, repo) = create_test_repo();
+        let ids: Vec<Oid> = ["aaaa", "bbbb", "cccc"]
+            .iter()
+            .map(|content| repo.blob(content.as_bytes()).unwrap())
+            .collect();
+
+        let mut cache = BlobCache::new(8);
+        for id in &ids {
+            cache.get(&repo, *id).unwrap();
+        }
+        assert_eq!(cache.entries.len(), 2);
+        assert!(!cache.entries.contains_key(&ids[0]));
+        assert_eq!(&*cache.get(&repo, ids[2]).unwrap(), b"cccc");
+        assert!(cache.get(&repo, Oid::zero()).is_err());
+
+        let mut disabled = BlobCache::disabled();
+        assert_eq!(&*disabled.get(&repo, ids[0]).unwrap(), b"aaaa");
+        assert_eq!(disabled.entries.len(), 0);
+    }
+}
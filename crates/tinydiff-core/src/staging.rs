@@ -0,0 +1,922 @@
+use crate::discard;
+use crate::error::CoreError;
+use crate::git::{
+    Snapshot, apply_diff_options, cached_file_versions, discover_repository, get_status_with_repo,
+    head_commit, patch_hunks, require_workdir, similar_source,
+};
+use crate::session::BlobCache;
+use crate::types::{
+    DiffHunk, DiffOptions, DiffTarget, FileEntry, FileEntryKind, GitStatus, HunkSelection,
+    LineChangeType, PatchAction,
+};
+use git2::{ApplyLocation, Delta, DiffLineType, Repository};
+use std::io::Write as _;
+use std::path::{Component, Path};
+
+fn validate_index_path(file_path: &str) -> Result<(), CoreError> {
+    let relative = Path::new(file_path)
+        .components()
+        .all(|c| matches!(c, Component::Normal(_) | Component::CurDir));
+    if file_path.is_empty() || !relative {
+        return Err(CoreError::InvalidPath(format!(
+            "Expected a path relative to the repository root: {file_path}"
+        )));
+    }
+    Ok(())
+}
+
+/// The paths an operation on `file_path` must cover, including the source of a rename.
+fn paths_with_rename_source(entries: &[FileEntry], file_path: &str) -> Vec<String> {
+    let mut paths = vec![file_path.to_owned()];
+    let rename_source = entries.iter().find_map(|entry| match &entry.kind {
+        FileEntryKind::Renamed { old_path, .. } if entry.path == file_path => Some(old_path),
+        _ => None,
+    });
+    paths.extend(rename_source.cloned());
+    paths
+}
+
+pub fn stage_file(repo_path: &Path, file_path: &str) -> Result<GitStatus, CoreError> {
+    let repo = discover_repository(repo_path)?;
+    stage_file_with_repo(&Snapshot::new(&repo), file_path)
+}
+
+pub(crate) fn stage_file_with_repo(
+    repo: &Snapshot,
+    file_path: &str,
+) -> Result<GitStatus, CoreError> {
+    validate_index_path(file_path)?;
+    let workdir = require_workdir(repo)?;
+
+    let status = get_status_with_repo(repo)?;
+    let mut index = repo.index()?;
+    for path in paths_with_rename_source(&status.unstaged, file_path) {
+        if workdir.join(&path).symlink_metadata().is_ok() {
+            index.add_path(Path::new(&path))?;
+        } else {
+            index.remove_path(Path::new(&path))?;
+        }
+    }
+    index.write()?;
+
+    get_status_with_repo(repo)
+}
+
+pub fn unstage_file(repo_path: &Path, file_path: &str) -> Result<GitStatus, CoreError> {
+    let repo = discover_repository(repo_path)?;
+    unstage_file_with_repo(&Snapshot::new(&repo), file_path)
+}
+
+pub(crate) fn unstage_file_with_repo(
+    repo: &Snapshot,
+    file_path: &str,
+) -> Result<GitStatus, CoreError> {
+    validate_index_path(file_path)?;
+
+    let status = get_status_with_repo(repo)?;
+    let paths = paths_with_rename_source(&status.staged, file_path);
+    if let Some(head) = head_commit(repo) {
+        repo.reset_default(Some(head.as_object()), &paths)?;
+    } else {
+        let mut index = repo.index()?;
+        for path in &paths {
+            index.remove_path(Path::new(path))?;
+        }
+        index.write()?;
+    }
+
+    get_status_with_repo(repo)
+}
+
+/// One side of a file as patch input: its raw lines without terminators, or `None` if absent.
+/// Lines are kept as bytes so files that aren't UTF-8 round-trip unchanged.
+struct PatchSide {
+    lines: Option<Vec<Vec<u8>>>,
+    missing_newline: bool,
+}
+
+impl PatchSide {
+    fn new(bytes: Option<&[u8]>) -> Self {
+        let missing_newline = bytes.is_some_and(|b| !b.is_empty() && !b.ends_with(b"\n"));
+        let lines = bytes.map(|b| {
+            b.split_inclusive(|&c| c == b'\n')
+                .map(|l| l.strip_suffix(b"\n").unwrap_or(l).to_vec())
+                .collect()
+        });
+        Self {
+            lines,
+            missing_newline,
+        }
+    }
+
+    /// The raw bytes of 1-based `line_no`.
+    fn line(&self, line_no: u32) -> Option<&[u8]> {
+        let idx = usize::try_from(line_no).ok()?.checked_sub(1)?;
+        self.lines.as_ref()?.get(idx).map(Vec::as_slice)
+    }
+
+    fn is_last_without_newline(&self, line_no: u32) -> bool {
+        self.missing_newline
+            && self
+                .lines
+                .as_ref()
+                .is_some_and(|lines| lines.len() == line_no as usize)
+    }
+}
+
+/// A hunk of the current diff, with the lines git2 reported as end-of-file newline markers.
+struct FreshHunk {
+    hunk: DiffHunk,
+    eof_markers: Vec<bool>,
+}
+
+fn fresh_hunks(
+    old_path: &str,
+    new_path: &str,
+    old: Option<&[u8]>,
+    new: Option<&[u8]>,
+    options: &DiffOptions,
+) -> Result<Vec<FreshHunk>, CoreError> {
+    let mut opts = git2::DiffOptions::new();
+    apply_diff_options(&mut opts, options);
+    let patch = git2::Patch::from_buffers(
+        old.unwrap_or_default(),
+        Some(Path::new(old_path)),
+        new.unwrap_or_default(),
+        Some(Path::new(new_path)),
+        Some(&mut opts),
+    )?;
+
+    let hunks = patch_hunks(&patch)?;
+    let mut fresh = Vec::with_capacity(hunks.len());
+    for (hunk_idx, hunk) in hunks.into_iter().enumerate() {
+        let eof_markers = (0..patch.num_lines_in_hunk(hunk_idx)?)
+            .map(|line_idx| {
+                let origin = patch.line_in_hunk(hunk_idx, line_idx)?.origin_value();
+                Ok(matches!(
+                    origin,
+                    DiffLineType::ContextEOFNL | DiffLineType::AddEOFNL | DiffLineType::DeleteEOFNL
+                ))
+            })
+            .collect::<Result<_, git2::Error>>()?;
+        fresh.push(FreshHunk { hunk, eof_markers });
+    }
+    Ok(fresh)
+}
+
+fn same_hunk(a: &DiffHunk, b: &DiffHunk) -> bool {
+    a.old_start == b.old_start
+        && a.old_lines == b.old_lines
+        && a.new_start == b.new_start
+        && a.new_lines == b.new_lines
+        && a.lines.len() == b.lines.len()
+        && a.lines.iter().zip(&b.lines).all(|(a, b)| {
+            a.change_type == b.change_type
+                && a.content == b.content
+                && a.old_line_no == b.old_line_no
+                && a.new_line_no == b.new_line_no
+        })
+}
+
+/// The end-of-file markers of the current hunk matching `selection`, which must be unchanged
+/// from header to context.
+fn check_selection<'a>(
+    file_path: &str,
+    selection: &HunkSelection,
+    fresh: &'a [FreshHunk],
+) -> Result<&'a [bool], CoreError> {
+    fresh
+        .iter()
+        .find(|f| same_hunk(&f.hunk, &selection.hunk))
+        .map(|f| f.eof_markers.as_slice())
+        .ok_or_else(|| CoreError::StaleSelection(file_path.to_owned()))
+}
+
+/// Mode for a file the patch creates, taken from the side its contents come from.
+fn created_mode(repo: &Repository, file_path: &str, action: PatchAction) -> Result<u32, CoreError> {
+    let path = Path::new(file_path);
+    let mode = match action {
+        PatchAction::Stage => {
+            let trust_mode = repo
+                .config()
+                .and_then(|c| c.get_bool("core.filemode"))
+                .unwrap_or(true);
+            let metadata = require_workdir(repo)?.join(path).symlink_metadata().ok();
+            metadata.map(|m| {
+                if m.file_type().is_symlink() {
+                    0o120_000
+                } else if trust_mode && is_executable(&m) {
+                    0o100_755
+                } else {
+                    0o100_644
+                }
+            })
+        }
+        PatchAction::Unstage => head_commit(repo)
+            .and_then(|c| c.tree().ok())
+            .and_then(|t| t.get_path(path).ok())
+            .and_then(|e| u32::try_from(e.filemode()).ok()),
+        PatchAction::Discard => repo.index()?.get_path(path, 0).map(|e| e.mode),
+    };
+    Ok(mode.unwrap_or(0o100_644))
+}
+
+#[cfg(unix)]
+fn is_executable(metadata: &std::fs::Metadata) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    metadata.permissions().mode() & 0o111 != 0
+}
+
+#[cfg(not(unix))]
+fn is_executable(_metadata: &std::fs::Metadata) -> bool {
+    false
+}
+
+/// Drops the index entry of a file HEAD lacks once every line has been unstaged.
+fn remove_if_emptied(repo: &Repository, file_path: &str) -> Result<(), CoreError> {
+    let mut index = repo.index()?;
+    let emptied = index
+        .get_path(Path::new(file_path), 0)
+        .is_some_and(|entry| repo.find_blob(entry.id).is_ok_and(|b| b.size() == 0));
+    if emptied {
+        index.remove_path(Path::new(file_path))?;
+        index.write()?;
+    }
+    Ok(())
+}
+
+/// Writes one hunk of a patch against `base`; `reverse` builds it from the new side back to the old.
+fn write_hunk(
+    patch: &mut Vec<u8>,
+    selection: &HunkSelection,
+    eof_markers: &[bool],
+    reverse: bool,
+    base: &PatchSide,
+    other: &PatchSide,
+    offset: &mut i64,
+) {
+    let hunk = &selection.hunk;
+    let mut body = Vec::new();
+    let mut base_count = 0u32;
+    let mut result_count = 0u32;
+    let mut changed = false;
+
+    for (idx, line) in hunk.lines.iter().enumerate() {
+        let (base_no, other_no) = if reverse {
+            (line.new_line_no, line.old_line_no)
+        } else {
+            (line.old_line_no, line.new_line_no)
+        };
+        if eof_markers.get(idx).copied().unwrap_or(false) {
+            continue;
+        }
+
+        let selected = selection
+            .lines
+            .as_ref()
+            .is_none_or(|lines| u32::try_from(idx).is_ok_and(|idx| lines.contains(&idx)));
+        let removes_from_base = match line.change_type {
+            LineChangeType::Context => None,
+            LineChangeType::Deletion => Some(!reverse),
+            LineChangeType::Addition => Some(reverse),
+        };
+
+        let (origin, side, line_no) = match (removes_from_base, selected) {
+            (Some(true), true) => ('-', base, base_no),
+            (None, _) | (Some(true), false) => (' ', base, base_no),
+            (Some(false), true) => ('+', other, other_no),
+            (Some(false), false) => continue,
+        };
+        match origin {
+            ' ' => {
+                base_count += 1;
+                result_count += 1;
+            }
+            '-' => base_count += 1,
+            _ => result_count += 1,
+        }
+        changed |= origin != ' ';
+
+        let content = line_no
+            .and_then(|n| side.line(n))
+            .unwrap_or(line.content.as_bytes());
+        let _ = write!(body, "{origin}");
+        body.extend_from_slice(content);
+        body.push(b'\n');
+        if line_no.is_some_and(|n| side.is_last_without_newline(n)) {
+            body.extend_from_slice(b"\\ No newline at end of file\n");
+        }
+    }
+
+    if !changed {
+        return;
+    }
+
+    let base_start = if reverse {
+        hunk.new_start
+    } else {
+        hunk.old_start
+    };
+    let result_start = i64::from(base_start) + *offset;
+    *offset += i64::from(result_count) - i64::from(base_count);
+
+    let _ = writeln!(
+        patch,
+        "@@ -{base_start},{base_count} +{result_start},{result_count} @@"
+    );
+    patch.extend_from_slice(&body);
+}
+
+/// Applies the selected hunks and lines like `git add -p`, rejecting selections that no
+/// longer match the file.
+/// `options` must be the ones the selected hunks were diffed with.
+pub fn apply_selection(
+    repo_path: &Path,
+    file_path: &str,
+    action: PatchAction,
+    selections: &[HunkSelection],
+    options: &DiffOptions,
+) -> Result<GitStatus, CoreError> {
+    let repo = discover_repository(repo_path)?;
+    apply_selection_with_repo(
+        &Snapshot::new(&repo),
+        file_path,
+        action,
+        selections,
+        options,
+    )
+}
+
+pub(crate) fn apply_selection_with_repo(
+    repo: &Snapshot,
+    file_path: &str,
+    action: PatchAction,
+    selections: &[HunkSelection],
+    options: &DiffOptions,
+) -> Result<GitStatus, CoreError> {
+    validate_index_path(file_path)?;
+
+    let (target, location, reverse) = match action {
+        PatchAction::Stage => (DiffTarget::Unstaged, ApplyLocation::Index, false),
+        PatchAction::Unstage => (DiffTarget::Staged, ApplyLocation::Index, true),
+        PatchAction::Discard => (DiffTarget::Unstaged, ApplyLocation::WorkDir, true),
+    };
+    let source = similar_source(repo, file_path, &target, options)?;
+    let old_path = source.as_ref().map_or(file_path, |s| s.old_path.as_str());
+    let (old_bytes, new_bytes) = cached_file_versions(
+        repo,
+        &mut BlobCache::disabled(),
+        old_path,
+        file_path,
+        &target,
+    )?;
+    let fresh = fresh_hunks(
+        old_path,
+        file_path,
+        old_bytes.as_deref(),
+        new_bytes.as_deref(),
+        options,
+    )?;
+    let mut checked = Vec::with_capacity(selections.len());
+    for selection in selections {
+        checked.push((selection, check_selection(file_path, selection, &fresh)?));
+    }
+
+    let old = PatchSide::new(old_bytes.as_deref());
+    let new = PatchSide::new(new_bytes.as_deref());
+    let (base, other) = if reverse { (&new, &old) } else { (&old, &new) };
+    checked.sort_by_key(|(s, _)| {
+        if reverse {
+            s.hunk.new_start
+        } else {
+            s.hunk.old_start
+        }
+    });
+
+    let mut hunks = Vec::new();
+    let mut offset = 0;
+    for (selection, eof_markers) in checked {
+        write_hunk(
+            &mut hunks,
+            selection,
+            eof_markers,
+            reverse,
+            base,
+            other,
+            &mut offset,
+        );
+    }
+    if hunks.is_empty() {
+        return get_status_with_repo(repo);
+    }
+
+    // Reversed patches edit the new path in place, so only staging carries a rename over.
+    let mut patch = Vec::new();
+    if let Some(source) = source.as_ref().filter(|_| !reverse) {
+        // libgit2 takes the paths from these headers and rejects `---`/`+++` after them.
+        let kind = if source.status == Delta::Copied {
+            "copy"
+        } else {
+            "rename"
+        };
+        let _ = write!(
+            patch,
+            "diff --git a/{old_path} b/{file_path}\n\
+             similarity index {}%\n\
+             {kind} from {old_path}\n\
+             {kind} to {file_path}\n",
+            source.similarity
+        );
+    } else {
+        let _ = writeln!(patch, "diff --git a/{file_path} b/{file_path}");
+        if base.lines.is_some() {
+            let _ = writeln!(patch, "--- a/{file_path}");
+        } else {
+            let mode = created_mode(repo, file_path, action)?;
+            let _ = writeln!(patch, "new file mode {mode:o}");
+            patch.extend_from_slice(b"--- /dev/null\n");
+        }
+        let _ = writeln!(patch, "+++ b/{file_path}");
+    }
+    patch.extend_from_slice(&hunks);
+
+    let diff = git2::Diff::from_buffer(&patch)?;
+    if action == PatchAction::Discard {
+        discard::snapshot(repo, file_path, true)?;
+    }
+    repo.apply(&diff, location, None)?;
+    if action == PatchAction::Unstage && old.lines.is_none() {
+        remove_if_emptied(repo, file_path)?;
+    }
+
+    get_status_with_repo(repo)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::get_file_diff;
+    use crate::test_support::{commit_file, commit_index, create_test_repo};
+    use crate::types::DiffContent;
+    use std::fs;
+
+    #[test]
+    fn test_stage_and_unstage_modified_and_untracked() {
+        let (temp_dir, repo) = create_test_repo();
+
+        commit_file(&repo, "file.txt", "one\n", "Initial commit");
+        fs::write(temp_dir.path().join("file.txt"), "two\n").unwrap();
+        fs::write(temp_dir.path().join("new.txt"), "new\n").unwrap();
+
+        stage_file(temp_dir.path(), "file.txt").unwrap();
+        let status = stage_file(temp_dir.path(), "new.txt").unwrap();
+        assert_eq!(status.staged.len(), 2);
+        assert!(status.unstaged.is_empty());
+        assert!(status.untracked.is_empty());
+
+        unstage_file(temp_dir.path(), "file.txt").unwrap();
+        let status = unstage_file(temp_dir.path(), "new.txt").unwrap();
+        assert!(status.staged.is_empty());
+        assert_eq!(status.unstaged.len(), 1);
+        assert_eq!(status.untracked.len(), 1);
+    }
+
+    #[test]
+    fn test_stage_deletion() {
+        let (temp_dir, repo) = create_test_repo();
+
+        commit_file(&repo, "file.txt", "one\n", "Initial commit");
+        fs::remove_file(temp_dir.path().join("file.txt")).unwrap();
+
+        let status = stage_file(temp_dir.path(), "file.txt").unwrap();
+        assert_eq!(status.staged[0].kind, FileEntryKind::Deleted);
+        assert!(status.unstaged.is_empty());
+    }
+
+    #[test]
+    fn test_stage_and_unstage_rename() {
+        let (temp_dir, repo) = create_test_repo();
+
+        let content = "line 1\nline 2\nline 3\nline 4\n";
+        commit_file(&repo, "old.txt", content, "Initial commit");
+        fs::rename(
+            temp_dir.path().join("old.txt"),
+            temp_dir.path().join("new.txt"),
+        )
+        .unwrap();
+
+        let status = stage_file(temp_dir.path(), "new.txt").unwrap();
+        assert_eq!(status.staged.len(), 1);
+        assert_eq!(status.staged[0].path, "new.txt");
+        assert!(matches!(
+            &status.staged[0].kind,
+            FileEntryKind::Renamed { old_path, .. } if old_path == "old.txt"
+        ));
+        assert!(status.unstaged.is_empty());
+
+        let status = unstage_file(temp_dir.path(), "new.txt").unwrap();
+        assert!(status.staged.is_empty());
+    }
+
+    #[test]
+    fn test_unstage_in_unborn_repo() {
+        let (temp_dir, _repo) = create_test_repo();
+
+        fs::write(temp_dir.path().join("file.txt"), "one\n").unwrap();
+        stage_file(temp_dir.path(), "file.txt").unwrap();
+
+        let status = unstage_file(temp_dir.path(), "file.txt").unwrap();
+        assert!(status.staged.is_empty());
+        assert_eq!(status.untracked.len(), 1);
+    }
+
+    #[test]
+    fn test_stage_rejects_escaping_path() {
+        let (temp_dir, _repo) = create_test_repo();
+
+        let result = stage_file(temp_dir.path(), "../outside.txt");
+        assert!(matches!(result, Err(CoreError::InvalidPath(_))));
+    }
+
+    fn numbered_lines(count: u32, changed: &[u32]) -> String {
+        (1..=count)
+            .map(|n| {
+                if changed.contains(&n) {
+                    format!("changed {n}\n")
+                } else {
+                    format!("line {n}\n")
+                }
+            })
+            .collect::<Vec<_>>()
+            .concat()
+    }
+
+    fn diff_hunks(repo_path: &Path, target: &DiffTarget) -> Vec<DiffHunk> {
+        path_hunks(repo_path, "file.txt", target)
+    }
+
+    fn path_hunks(repo_path: &Path, path: &str, target: &DiffTarget) -> Vec<DiffHunk> {
+        let diff = get_file_diff(repo_path, path, target, &DiffOptions::default()).unwrap();
+        let DiffContent::Text { hunks } = diff.content else {
+            unreachable!()
+        };
+        hunks
+    }
+
+    fn whole(hunk: &DiffHunk) -> HunkSelection {
+        HunkSelection {
+            hunk: hunk.clone(),
+            lines: None,
+        }
+    }
+
+    fn index_bytes(repo: &Repository, path: &str) -> Vec<u8> {
+        let mut index = repo.index().unwrap();
+        index.read(true).unwrap();
+        let entry = index.get_path(Path::new(path), 0).unwrap();
+        repo.find_blob(entry.id).unwrap().content().to_vec()
+    }
+
+    fn index_contents(repo: &Repository, path: &str) -> String {
+        String::from_utf8(index_bytes(repo, path)).unwrap()
+    }
+
+    #[test]
+    fn test_stage_and_unstage_single_hunk() {
+        let (temp_dir, repo) = create_test_repo();
+
+        commit_file(
+            &repo,
+            "file.txt",
+            &numbered_lines(20, &[]),
+            "Initial commit",
+        );
+        fs::write(
+            temp_dir.path().join("file.txt"),
+            numbered_lines(20, &[2, 18]),
+        )
+        .unwrap();
+
+        let hunks = diff_hunks(temp_dir.path(), &DiffTarget::Unstaged);
+        assert_eq!(hunks.len(), 2);
+
+        apply_selection(
+            temp_dir.path(),
+            "file.txt",
+            PatchAction::Stage,
+            &[whole(&hunks[1])],
+            &DiffOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(index_contents(&repo, "file.txt"), numbered_lines(20, &[18]));
+
+        let staged = diff_hunks(temp_dir.path(), &DiffTarget::Staged);
+        apply_selection(
+            temp_dir.path(),
+            "file.txt",
+            PatchAction::Unstage,
+            &[whole(&staged[0])],
+            &DiffOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(index_contents(&repo, "file.txt"), numbered_lines(20, &[]));
+    }
+
+    #[test]
+    fn test_stage_and_unstage_hunk_of_renamed_file() {
+        let (temp_dir, repo) = create_test_repo();
+
+        commit_file(&repo, "old.txt", &numbered_lines(20, &[]), "Initial commit");
+        fs::remove_file(temp_dir.path().join("old.txt")).unwrap();
+        fs::write(
+            temp_dir.path().join("new.txt"),
+            numbered_lines(20, &[2, 18]),
+        )
+        .unwrap();
+
+        let hunks = path_hunks(temp_dir.path(), "new.txt", &DiffTarget::Unstaged);
+        assert_eq!(hunks.len(), 2);
+        let status = apply_selection(
+            temp_dir.path(),
+            "new.txt",
+            PatchAction::Stage,
+            &[whole(&hunks[1])],
+            &DiffOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(index_contents(&repo, "new.txt"), numbered_lines(20, &[18]));
+        assert!(matches!(
+            &status.staged[..],
+            [FileEntry { path, kind: FileEntryKind::Renamed { old_path, .. }, .. }]
+                if path == "new.txt" && old_path == "old.txt"
+        ));
+
+        stage_file(temp_dir.path(), "new.txt").unwrap();
+        let staged = path_hunks(temp_dir.path(), "new.txt", &DiffTarget::Staged);
+        assert_eq!(staged.len(), 2);
+        let status = apply_selection(
+            temp_dir.path(),
+            "new.txt",
+            PatchAction::Unstage,
+            &[whole(&staged[0])],
+            &DiffOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(index_contents(&repo, "new.txt"), numbered_lines(20, &[18]));
+        assert!(matches!(
+            &status.staged[..],
+            [FileEntry { kind: FileEntryKind::Renamed { old_path, .. }, .. }] if old_path == "old.txt"
+        ));
+    }
+
+    #[test]
+    fn test_stage_selected_lines() {
+        let (temp_dir, repo) = create_test_repo();
+
+        commit_file(&repo, "file.txt", "a\nb\nc\n", "Initial commit");
+        fs::write(temp_dir.path().join("file.txt"), "a\nB\nc\nd\n").unwrap();
+
+        let hunks = diff_hunks(temp_dir.path(), &DiffTarget::Unstaged);
+        let added = hunks[0]
+            .lines
+            .iter()
+            .position(|l| l.content == "d")
+            .unwrap();
+        let selection = HunkSelection {
+            hunk: hunks[0].clone(),
+            lines: Some(vec![u32::try_from(added).unwrap()]),
+        };
+
+        let status = apply_selection(
+            temp_dir.path(),
+            "file.txt",
+            PatchAction::Stage,
+            &[selection],
+            &DiffOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(index_contents(&repo, "file.txt"), "a\nb\nc\nd\n");
+        assert_eq!(status.staged.len(), 1);
+        assert_eq!(status.unstaged.len(), 1);
+    }
+
+    #[test]
+    fn test_discard_selected_lines() {
+        let (temp_dir, repo) = create_test_repo();
+
+        commit_file(&repo, "file.txt", "a\nb\nc\n", "Initial commit");
+        fs::write(temp_dir.path().join("file.txt"), "a\nB\nc\nd\n").unwrap();
+
+        let hunks = diff_hunks(temp_dir.path(), &DiffTarget::Unstaged);
+        let changed: Vec<u32> = hunks[0]
+            .lines
+            .iter()
+            .enumerate()
+            .filter(|(_, l)| l.content == "b" || l.content == "B")
+            .map(|(idx, _)| u32::try_from(idx).unwrap())
+            .collect();
+        let selection = HunkSelection {
+            hunk: hunks[0].clone(),
+            lines: Some(changed),
+        };
+
+        apply_selection(
+            temp_dir.path(),
+            "file.txt",
+            PatchAction::Discard,
+            &[selection],
+            &DiffOptions::default(),
+        )
+        .unwrap();
+        let contents = fs::read_to_string(temp_dir.path().join("file.txt")).unwrap();
+        assert_eq!(contents, "a\nb\nc\nd\n");
+    }
+
+    #[test]
+    fn test_stage_lines_of_untracked_file() {
+        let (temp_dir, repo) = create_test_repo();
+
+        commit_file(&repo, "other.txt", "x\n", "Initial commit");
+        fs::write(temp_dir.path().join("file.txt"), "one\ntwo\n").unwrap();
+
+        let hunks = diff_hunks(temp_dir.path(), &DiffTarget::Unstaged);
+        let selection = HunkSelection {
+            hunk: hunks[0].clone(),
+            lines: Some(vec![0]),
+        };
+
+        apply_selection(
+            temp_dir.path(),
+            "file.txt",
+            PatchAction::Stage,
+            &[selection],
+            &DiffOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(index_contents(&repo, "file.txt"), "one\n");
+    }
+
+    #[test]
+    fn test_apply_selection_rejects_stale_hunk() {
+        let (temp_dir, repo) = create_test_repo();
+
+        commit_file(&repo, "file.txt", "a\nb\n", "Initial commit");
+        fs::write(temp_dir.path().join("file.txt"), "a\nB\n").unwrap();
+        let hunks = diff_hunks(temp_dir.path(), &DiffTarget::Unstaged);
+        fs::write(temp_dir.path().join("file.txt"), "a\nC\n").unwrap();
+
+        let result = apply_selection(
+            temp_dir.path(),
+            "file.txt",
+            PatchAction::Stage,
+            &[whole(&hunks[0])],
+            &DiffOptions::default(),
+        );
+        assert!(matches!(result, Err(CoreError::StaleSelection(_))));
+        assert_eq!(index_contents(&repo, "file.txt"), "a\nb\n");
+    }
+
+    #[test]
+    fn test_stage_hunk_without_trailing_newline() {
+        let (temp_dir, repo) = create_test_repo();
+
+        commit_file(&repo, "file.txt", "a\nb", "Initial commit");
+        fs::write(temp_dir.path().join("file.txt"), "a\nB").unwrap();
+
+        let hunks = diff_hunks(temp_dir.path(), &DiffTarget::Unstaged);
+        apply_selection(
+            temp_dir.path(),
+            "file.txt",
+            PatchAction::Stage,
+            &[whole(&hunks[0])],
+            &DiffOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(index_contents(&repo, "file.txt"), "a\nB");
+    }
+
+    #[test]
+    fn test_stage_and_discard_hunk_of_non_utf8_file() {
+        let (temp_dir, repo) = create_test_repo();
+
+        let file = temp_dir.path().join("file.txt");
+        fs::write(&file, b"caf\xE9\n").unwrap();
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new("file.txt")).unwrap();
+        index.write().unwrap();
+        commit_index(&repo, "Initial commit");
+
+        fs::write(&file, b"caf\xE9\nna\xEFve\n").unwrap();
+        let hunks = diff_hunks(temp_dir.path(), &DiffTarget::Unstaged);
+        apply_selection(
+            temp_dir.path(),
+            "file.txt",
+            PatchAction::Stage,
+            &[whole(&hunks[0])],
+            &DiffOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(index_bytes(&repo, "file.txt"), b"caf\xE9\nna\xEFve\n");
+
+        fs::write(&file, b"caf\xE9\nna\xEFve\nr\xE9sum\xE9\n").unwrap();
+        let hunks = diff_hunks(temp_dir.path(), &DiffTarget::Unstaged);
+        apply_selection(
+            temp_dir.path(),
+            "file.txt",
+            PatchAction::Discard,
+            &[whole(&hunks[0])],
+            &DiffOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(fs::read(&file).unwrap(), b"caf\xE9\nna\xEFve\n");
+    }
+
+    #[test]
+    fn test_apply_selection_rejects_hunk_with_changed_context() {
+        let (temp_dir, repo) = create_test_repo();
+
+        commit_file(
+            &repo,
+            "file.txt",
+            &numbered_lines(20, &[]),
+            "Initial commit",
+        );
+        fs::write(temp_dir.path().join("file.txt"), numbered_lines(20, &[10])).unwrap();
+        let hunks = diff_hunks(temp_dir.path(), &DiffTarget::Unstaged);
+        fs::write(
+            temp_dir.path().join("file.txt"),
+            numbered_lines(20, &[10, 13]),
+        )
+        .unwrap();
+
+        let result = apply_selection(
+            temp_dir.path(),
+            "file.txt",
+            PatchAction::Stage,
+            &[whole(&hunks[0])],
+            &DiffOptions::default(),
+        );
+        assert!(matches!(result, Err(CoreError::StaleSelection(_))));
+        assert_eq!(index_contents(&repo, "file.txt"), numbered_lines(20, &[]));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_stage_lines_of_untracked_file_keeps_mode() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let (temp_dir, repo) = create_test_repo();
+        commit_file(&repo, "other.txt", "x\n", "Initial commit");
+        let script = temp_dir.path().join("file.txt");
+        fs::write(&script, "#!/bin/sh\necho hi\n").unwrap();
+        fs::set_permissions(&script, fs::Permissions::from_mode(0o755)).unwrap();
+
+        let hunks = diff_hunks(temp_dir.path(), &DiffTarget::Unstaged);
+        let selection = HunkSelection {
+            hunk: hunks[0].clone(),
+            lines: Some(vec![0]),
+        };
+        apply_selection(
+            temp_dir.path(),
+            "file.txt",
+            PatchAction::Stage,
+            &[selection],
+            &DiffOptions::default(),
+        )
+        .unwrap();
+
+        let mut index = repo.index().unwrap();
+        index.read(true).unwrap();
+        let entry = index.get_path(Path::new("file.txt"), 0).unwrap();
+        assert_eq!(entry.mode, 0o100_755);
+    }
+
+    #[test]
+    fn test_unstage_every_line_of_added_file_removes_entry() {
+        let (temp_dir, repo) = create_test_repo();
+
+        commit_file(&repo, "other.txt", "x\n", "Initial commit");
+        fs::write(temp_dir.path().join("file.txt"), "one\ntwo\n").unwrap();
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new("file.txt")).unwrap();
+        index.write().unwrap();
+
+        let hunks = diff_hunks(temp_dir.path(), &DiffTarget::Staged);
+        let selection = HunkSelection {
+            hunk: hunks[0].clone(),
+            lines: Some(vec![0, 1]),
+        };
+        let status = apply_selection(
+            temp_dir.path(),
+            "file.txt",
+            PatchAction::Unstage,
+            &[selection],
+            &DiffOptions::default(),
+        )
+        .unwrap();
+
+        assert!(status.staged.is_empty());
+        assert_eq!(status.untracked.len(), 1);
+        assert_eq!(status.untracked[0].path, "file.txt");
+    }
+}
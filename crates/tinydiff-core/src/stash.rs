@@ -0,0 +1,194 @@
+use crate::error::CoreError;
+use crate::git::{Snapshot, discover_repository, get_status_with_repo};
+use crate::history::signature_info;
+use crate::types::{GitStatus, StashEntry};
+use git2::{Oid, Repository, StashFlags};
+use std::path::Path;
+
+pub(crate) fn list_stashes_with_repo(repo: &mut Repository) -> Result<Vec<StashEntry>, CoreError> {
+    let mut stashes: Vec<(usize, String, Oid)> = Vec::new();
+    repo.stash_foreach(|index, message, id| {
+        stashes.push((index, message.to_owned(), *id));
+        true
+    })?;
+
+    stashes
+        .into_iter()
+        .map(|(index, message, id)| {
+            let commit = repo.find_commit(id)?;
+            Ok(StashEntry {
+                index: u32::try_from(index).unwrap_or(u32::MAX),
+                id: id.to_string(),
+                message,
+                author: signature_info(&commit.author()),
+            })
+        })
+        .collect()
+}
+
+/// Stashes, most recent (`stash@{0}`) first.
+pub fn list_stashes(repo_path: &Path) -> Result<Vec<StashEntry>, CoreError> {
+    let mut repo = discover_repository(repo_path)?;
+    list_stashes_with_repo(&mut repo)
+}
+
+pub fn create_stash(
+    repo_path: &Path,
+    message: Option<&str>,
+    include_untracked: bool,
+) -> Result<StashEntry, CoreError> {
+    let mut repo = discover_repository(repo_path)?;
+    create_stash_with_repo(&mut repo, message, include_untracked)
+}
+
+pub(crate) fn create_stash_with_repo(
+    repo: &mut Repository,
+    message: Option<&str>,
+    include_untracked: bool,
+) -> Result<StashEntry, CoreError> {
+    let signature = repo.signature()?.to_owned();
+    let flags = if include_untracked {
+        StashFlags::INCLUDE_UNTRACKED
+    } else {
+        StashFlags::DEFAULT
+    };
+    let id = repo.stash_save2(&signature, message, Some(flags))?;
+
+    list_stashes_with_repo(repo)?
+        .into_iter()
+        .find(|entry| entry.id == id.to_string())
+        .ok_or_else(|| CoreError::InvalidPath(format!("Stash {id} was not recorded")))
+}
+
+pub fn apply_stash(repo_path: &Path, index: u32) -> Result<GitStatus, CoreError> {
+    let mut repo = discover_repository(repo_path)?;
+    apply_stash_with_repo(&mut repo, index, false)
+}
+
+pub fn pop_stash(repo_path: &Path, index: u32) -> Result<GitStatus, CoreError> {
+    let mut repo = discover_repository(repo_path)?;
+    apply_stash_with_repo(&mut repo, index, true)
+}
+
+/// Applies `stash@{index}` to the working tree, dropping it afterwards when `pop` is set.
+pub(crate) fn apply_stash_with_repo(
+    repo: &mut Repository,
+    index: u32,
+    pop: bool,
+) -> Result<GitStatus, CoreError> {
+    if pop {
+        repo.stash_pop(index as usize, None)?;
+    } else {
+        repo.stash_apply(index as usize, None)?;
+    }
+    get_status_with_repo(&Snapshot::new(repo))
+}
+
+pub fn drop_stash(repo_path: &Path, index: u32) -> Result<Vec<StashEntry>, CoreError> {
+    let mut repo = discover_repository(repo_path)?;
+    drop_stash_with_repo(&mut repo, index)
+}
+
+pub(crate) fn drop_stash_with_repo(
+    repo: &mut Repository,
+    index: u32,
+) -> Result<Vec<StashEntry>, CoreError> {
+    repo.stash_drop(index as usize)?;
+    list_stashes_with_repo(repo)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::{get_changed_files, get_file_diff};
+    use crate::test_support::{commit_file, create_test_repo};
+    use crate::types::{DiffContent, DiffOptions, DiffTarget, FileEntryKind, LineChangeType};
+    use std::fs;
+
+    #[test]
+    fn test_create_and_list_stashes() {
+        let (temp_dir, repo) = create_test_repo();
+
+        commit_file(&repo, "file.txt", "one\n", "Initial commit");
+        fs::write(temp_dir.path().join("file.txt"), "two\n").unwrap();
+        create_stash(temp_dir.path(), Some("first"), false).unwrap();
+        fs::write(temp_dir.path().join("file.txt"), "three\n").unwrap();
+        let entry = create_stash(temp_dir.path(), Some("second"), false).unwrap();
+
+        assert_eq!(entry.index, 0);
+        assert!(entry.message.ends_with("second"));
+        assert_eq!(entry.author.name, "Test User");
+
+        let stashes = list_stashes(temp_dir.path()).unwrap();
+        let messages: Vec<_> = stashes.iter().map(|s| s.message.as_str()).collect();
+        assert_eq!(messages.len(), 2);
+        assert!(messages[1].ends_with("first"));
+        assert_eq!(
+            fs::read_to_string(temp_dir.path().join("file.txt")).unwrap(),
+            "one\n"
+        );
+    }
+
+    #[test]
+    fn test_stash_diff_includes_untracked_files() {
+        let (temp_dir, repo) = create_test_repo();
+
+        commit_file(&repo, "file.txt", "one\n", "Initial commit");
+        fs::write(temp_dir.path().join("file.txt"), "two\n").unwrap();
+        fs::write(temp_dir.path().join("new.txt"), "new\n").unwrap();
+        create_stash(temp_dir.path(), None, true).unwrap();
+        assert!(!temp_dir.path().join("new.txt").exists());
+
+        let target = DiffTarget::Stash { index: 0 };
+        let mut files = get_changed_files(temp_dir.path(), &target, &DiffOptions::default())
+            .unwrap()
+            .into_iter()
+            .map(|e| (e.path, e.kind))
+            .collect::<Vec<_>>();
+        files.sort_by(|a, b| a.0.cmp(&b.0));
+        assert_eq!(
+            files,
+            vec![
+                ("file.txt".to_string(), FileEntryKind::Modified),
+                ("new.txt".to_string(), FileEntryKind::Added),
+            ]
+        );
+
+        let diff =
+            get_file_diff(temp_dir.path(), "new.txt", &target, &DiffOptions::default()).unwrap();
+        let DiffContent::Text { hunks } = diff.content else {
+            unreachable!()
+        };
+        assert_eq!(hunks[0].lines[0].change_type, LineChangeType::Addition);
+        assert_eq!(hunks[0].lines[0].content, "new");
+    }
+
+    #[test]
+    fn test_apply_pop_and_drop_stash() {
+        let (temp_dir, repo) = create_test_repo();
+
+        commit_file(&repo, "file.txt", "one\n", "Initial commit");
+        fs::write(temp_dir.path().join("file.txt"), "two\n").unwrap();
+        create_stash(temp_dir.path(), Some("keep"), false).unwrap();
+
+        let status = apply_stash(temp_dir.path(), 0).unwrap();
+        assert_eq!(status.unstaged.len(), 1);
+        assert_eq!(list_stashes(temp_dir.path()).unwrap().len(), 1);
+
+        fs::write(temp_dir.path().join("file.txt"), "one\n").unwrap();
+        pop_stash(temp_dir.path(), 0).unwrap();
+        assert!(list_stashes(temp_dir.path()).unwrap().is_empty());
+        assert_eq!(
+            fs::read_to_string(temp_dir.path().join("file.txt")).unwrap(),
+            "two\n"
+        );
+
+        create_stash(temp_dir.path(), Some("drop me"), false).unwrap();
+        let remaining = drop_stash(temp_dir.path(), 0).unwrap();
+        assert!(remaining.is_empty());
+        assert_eq!(
+            fs::read_to_string(temp_dir.path().join("file.txt")).unwrap(),
+            "one\n"
+        );
+    }
+}
@@ -0,0 +1,205 @@
+use crate::error::CoreError;
+use crate::git::{Snapshot, diff_for_target, discover_repository, is_gitlink, nonzero_id};
+use crate::history::commit_info;
+use crate::types::{CommitInfo, DiffTarget, SubmoduleDiff};
+use git2::{Oid, Repository, Sort};
+use std::path::Path;
+
+const MAX_SUBMODULE_COMMITS: usize = 100;
+
+/// Up to `limit` commits reachable from `from` but not from `hide`, newest first, and whether
+/// any were left out.
+fn commits_between(
+    repo: &Repository,
+    from: Oid,
+    hide: Oid,
+    limit: usize,
+) -> Result<(Vec<CommitInfo>, bool), CoreError> {
+    let mut revwalk = repo.revwalk()?;
+    revwalk.set_sorting(Sort::TOPOLOGICAL | Sort::TIME)?;
+    revwalk.push(from)?;
+    revwalk.hide(hide)?;
+
+    let mut commits = Vec::new();
+    for id in revwalk {
+        if commits.len() == limit {
+            return Ok((commits, true));
+        }
+        commits.push(commit_info(&repo.find_commit(id?)?));
+    }
+    Ok((commits, false))
+}
+
+/// Describes how the submodule at `submodule_path` moved in `target`, with the commits gained
+/// and lost between its old and new pointers.
+pub fn get_submodule_diff(
+    repo_path: &Path,
+    submodule_path: &str,
+    target: &DiffTarget,
+) -> Result<SubmoduleDiff, CoreError> {
+    let repo = discover_repository(repo_path)?;
+    get_submodule_diff_with_repo(&Snapshot::new(&repo), submodule_path, target)
+}
+
+pub(crate) fn get_submodule_diff_with_repo(
+    repo: &Snapshot,
+    submodule_path: &str,
+    target: &DiffTarget,
+) -> Result<SubmoduleDiff, CoreError> {
+    let mut opts = git2::DiffOptions::new();
+    opts.pathspec(submodule_path).disable_pathspec_match(true);
+    let diff = diff_for_target(repo, target, &mut opts)?;
+    let (old_id, new_id) = diff
+        .deltas()
+        .find(is_gitlink)
+        .map(|d| (d.old_file().id(), d.new_file().id()))
+        .ok_or_else(|| {
+            CoreError::InvalidPath(format!("No submodule change at {submodule_path}"))
+        })?;
+
+    let nested = repo
+        .find_submodule(submodule_path)
+        .and_then(|s| s.open())
+        .ok();
+    let ((added, added_truncated), (removed, removed_truncated)) = match &nested {
+        Some(nested)
+            if !old_id.is_zero()
+                && !new_id.is_zero()
+                && nested.find_commit(old_id).is_ok()
+                && nested.find_commit(new_id).is_ok() =>
+        {
+            (
+                commits_between(nested, new_id, old_id, MAX_SUBMODULE_COMMITS)?,
+                commits_between(nested, old_id, new_id, MAX_SUBMODULE_COMMITS)?,
+            )
+        }
+        _ => ((Vec::new(), false), (Vec::new(), false)),
+    };
+
+    Ok(SubmoduleDiff {
+        path: submodule_path.to_owned(),
+        repo_path: nested
+            .as_ref()
+            .and_then(|r| r.workdir())
+            .map(|p| p.to_string_lossy().into_owned()),
+        old_commit: nonzero_id(old_id),
+        new_commit: nonzero_id(new_id),
+        added,
+        removed,
+        truncated: added_truncated || removed_truncated,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::get_status;
+    use crate::test_support::{commit_file, commit_index, create_test_repo};
+    use crate::types::FileEntryKind;
+    use git2::{IndexEntry, IndexTime};
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn stage_gitlink(repo: &Repository, path: &str, id: Oid) {
+        let mut index = repo.index().unwrap();
+        index
+            .add(&IndexEntry {
+                ctime: IndexTime::new(0, 0),
+                mtime: IndexTime::new(0, 0),
+                dev: 0,
+                ino: 0,
+                mode: 0o160_000,
+                uid: 0,
+                gid: 0,
+                file_size: 0,
+                id,
+                flags: 0,
+                flags_extended: 0,
+                path: path.as_bytes().to_vec(),
+            })
+            .unwrap();
+        index.write().unwrap();
+    }
+
+    /// Commits a superproject containing `sub` at the nested repository's first commit.
+    fn create_repo_with_submodule() -> (TempDir, Repository, Repository) {
+        let (temp_dir, repo) = create_test_repo();
+        let sub = Repository::init(temp_dir.path().join("sub")).unwrap();
+        let first = commit_file(&sub, "lib.txt", "v1\n", "Sub v1");
+
+        fs::write(
+            temp_dir.path().join(".gitmodules"),
+            "[submodule \"sub\"]\n\tpath = sub\n\turl = ./sub\n",
+        )
+        .unwrap();
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new(".gitmodules")).unwrap();
+        index.write().unwrap();
+        stage_gitlink(&repo, "sub", first);
+        commit_index(&repo, "Add submodule");
+
+        (temp_dir, repo, sub)
+    }
+
+    #[test]
+    fn test_status_reports_moved_and_dirty_submodule() {
+        let (temp_dir, repo, sub) = create_repo_with_submodule();
+        let first = sub.head().unwrap().target().unwrap();
+        let second = commit_file(&sub, "lib.txt", "v2\n", "Sub v2");
+        fs::write(temp_dir.path().join("sub").join("scratch.txt"), "x").unwrap();
+
+        let status = get_status(temp_dir.path()).unwrap();
+        let entry = status.unstaged.iter().find(|e| e.path == "sub").unwrap();
+        assert_eq!(
+            entry.kind,
+            FileEntryKind::Submodule {
+                old_commit: Some(first.to_string()),
+                new_commit: Some(second.to_string()),
+                modified_content: false,
+                untracked_content: true,
+            }
+        );
+
+        stage_gitlink(&repo, "sub", second);
+        let status = get_status(temp_dir.path()).unwrap();
+        let entry = status.staged.iter().find(|e| e.path == "sub").unwrap();
+        assert!(matches!(
+            &entry.kind,
+            FileEntryKind::Submodule { new_commit: Some(id), .. } if *id == second.to_string()
+        ));
+    }
+
+    #[test]
+    fn test_get_submodule_diff_lists_commits() {
+        let (temp_dir, _repo, sub) = create_repo_with_submodule();
+        commit_file(&sub, "lib.txt", "v2\n", "Sub v2");
+        commit_file(&sub, "lib.txt", "v3\n", "Sub v3");
+
+        let diff = get_submodule_diff(temp_dir.path(), "sub", &DiffTarget::Unstaged).unwrap();
+        let summaries: Vec<&str> = diff.added.iter().map(|c| c.summary.as_str()).collect();
+        assert_eq!(summaries, vec!["Sub v3", "Sub v2"]);
+        assert!(diff.removed.is_empty());
+        assert!(!diff.truncated);
+
+        let nested_path = diff.repo_path.unwrap();
+        let nested_status = get_status(Path::new(&nested_path)).unwrap();
+        assert!(nested_status.unstaged.is_empty());
+    }
+
+    #[test]
+    fn test_commits_between_stops_at_limit() {
+        let (_temp_dir, _repo, sub) = create_repo_with_submodule();
+        let first = sub.head().unwrap().target().unwrap();
+        commit_file(&sub, "lib.txt", "v2\n", "Sub v2");
+        let last = commit_file(&sub, "lib.txt", "v3\n", "Sub v3");
+
+        let (commits, truncated) = commits_between(&sub, last, first, 1).unwrap();
+        assert_eq!(commits.len(), 1);
+        assert_eq!(commits[0].summary, "Sub v3");
+        assert!(truncated);
+
+        let (commits, truncated) = commits_between(&sub, last, first, 2).unwrap();
+        assert_eq!(commits.len(), 2);
+        assert!(!truncated);
+    }
+}
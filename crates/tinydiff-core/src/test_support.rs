@@ -0,0 +1,45 @@
+use git2::{Oid, Repository, Signature};
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+pub(crate) fn init_test_repo(path: &Path) -> Repository {
+    let repo = Repository::init(path).unwrap();
+
+    let mut config = repo.config().unwrap();
+    config.set_str("user.name", "Test User").unwrap();
+    config.set_str("user.email", "test@example.com").unwrap();
+
+    repo
+}
+
+pub(crate) fn create_test_repo() -> (TempDir, Repository) {
+    let temp_dir = TempDir::new().unwrap();
+    let repo = init_test_repo(temp_dir.path());
+    (temp_dir, repo)
+}
+
+/// Commits the current index on top of HEAD.
+pub(crate) fn commit_index(repo: &Repository, message: &str) -> Oid {
+    let mut index = repo.index().unwrap();
+    let tree_id = index.write_tree().unwrap();
+    let tree = repo.find_tree(tree_id).unwrap();
+    let sig = Signature::now("Test User", "test@example.com").unwrap();
+
+    let parent = repo.head().ok().and_then(|h| h.peel_to_commit().ok());
+    let parents: Vec<&git2::Commit> = parent.iter().collect();
+
+    repo.commit(Some("HEAD"), &sig, &sig, message, &tree, &parents)
+        .unwrap()
+}
+
+pub(crate) fn commit_file(repo: &Repository, path: &str, content: &str, message: &str) -> Oid {
+    let repo_path = repo.workdir().unwrap();
+    fs::write(repo_path.join(path), content).unwrap();
+
+    let mut index = repo.index().unwrap();
+    index.add_path(Path::new(path)).unwrap();
+    index.write().unwrap();
+
+    commit_index(repo, message)
+}
@@ -10,10 +10,25 @@ pub enum FileEntryKind {
     Added,
     Modified,
     Deleted,
-    Renamed { old_path: String },
+    Renamed {
+        old_path: String,
+        similarity: u16,
+    },
+    Copied {
+        old_path: String,
+        similarity: u16,
+    },
     Untracked,
     Typechange,
     Conflicted,
+    /// A gitlink change; the dirty flags describe the submodule's own working tree.
+    #[serde(rename_all = "camelCase")]
+    Submodule {
+        old_commit: Option<String>,
+        new_commit: Option<String>,
+        modified_content: bool,
+        untracked_content: bool,
+    },
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -22,15 +37,75 @@ pub enum FileEntryKind {
 pub struct FileEntry {
     pub path: String,
     pub kind: FileEntryKind,
+    /// `None` where line counts aren't computed, such as file history entries.
+    pub stats: Option<DiffStats>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "specta", derive(Type))]
+#[serde(rename_all = "camelCase")]
+pub struct DiffStats {
+    pub insertions: u32,
+    pub deletions: u32,
+    pub is_binary: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "specta", derive(Type))]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum HeadState {
+    Branch {
+        name: String,
+    },
+    #[serde(rename_all = "camelCase")]
+    Detached {
+        commit_id: String,
+    },
+    /// A branch with no commits yet.
+    Unborn {
+        name: String,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "specta", derive(Type))]
+#[serde(rename_all = "camelCase")]
+pub struct UpstreamStatus {
+    pub name: String,
+    pub ahead: u32,
+    pub behind: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "specta", derive(Type))]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum RepoOperation {
+    Merge,
+    /// `step` and `total` are unknown when git hasn't written its progress files yet.
+    Rebase {
+        step: Option<u32>,
+        total: Option<u32>,
+    },
+    CherryPick,
+    Revert,
+    Bisect,
+    ApplyMailbox,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[cfg_attr(feature = "specta", derive(Type))]
 #[serde(rename_all = "camelCase")]
 pub struct GitStatus {
+    /// Bare repositories have no index or working tree, so the file lists are always empty.
+    pub bare: bool,
+    pub head: HeadState,
+    pub upstream: Option<UpstreamStatus>,
+    pub operation: Option<RepoOperation>,
     pub staged: Vec<FileEntry>,
     pub unstaged: Vec<FileEntry>,
     pub untracked: Vec<FileEntry>,
+    /// Staged, unstaged and untracked changes merged per path, relative to HEAD.
+    pub uncommitted: Vec<FileEntry>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -42,6 +117,16 @@ pub enum LineChangeType {
     Deletion,
 }
 
+/// A changed span within a line, in UTF-16 code unit offsets (`start` inclusive, `end`
+/// exclusive) so JavaScript can slice the line directly.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "specta", derive(Type))]
+#[serde(rename_all = "camelCase")]
+pub struct InlineChange {
+    pub start: u32,
+    pub end: u32,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[cfg_attr(feature = "specta", derive(Type))]
 #[serde(rename_all = "camelCase")]
@@ -50,6 +135,22 @@ pub struct DiffLine {
     pub content: String,
     pub old_line_no: Option<u32>,
     pub new_line_no: Option<u32>,
+    pub inline_changes: Vec<InlineChange>,
+    /// Attribution of the old-side line; only set on deletions when `blame_deletions` is enabled.
+    pub blame: Option<LineBlame>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "specta", derive(Type))]
+#[serde(tag = "state", rename_all = "camelCase")]
+pub enum LineBlame {
+    #[serde(rename_all = "camelCase")]
+    Committed {
+        commit_id: String,
+        summary: String,
+        author: CommitSignature,
+    },
+    Uncommitted,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -64,6 +165,35 @@ pub struct DiffHunk {
     pub lines: Vec<DiffLine>,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "specta", derive(Type))]
+#[serde(rename_all = "camelCase")]
+pub enum PatchAction {
+    /// Applies changes from the `Unstaged` diff to the index.
+    Stage,
+    /// Reverts changes in the `Staged` diff from the index.
+    Unstage,
+    /// Reverts changes in the `Unstaged` diff from the working tree.
+    Discard,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "specta", derive(Type))]
+#[serde(rename_all = "camelCase")]
+pub struct HunkSelection {
+    pub hunk: DiffHunk,
+    /// Indices into `hunk.lines`; `None` selects the whole hunk.
+    pub lines: Option<Vec<u32>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "specta", derive(Type))]
+#[serde(rename_all = "lowercase")]
+pub enum DiffSide {
+    Old,
+    New,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[cfg_attr(feature = "specta", derive(Type))]
 #[serde(tag = "contentType", rename_all = "camelCase")]
@@ -78,15 +208,190 @@ pub enum DiffContent {
 pub struct FileDiff {
     pub path: String,
     pub old_path: Option<String>,
+    pub similarity: Option<u16>,
     pub content: DiffContent,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "specta", derive(Type))]
+#[serde(rename_all = "camelCase")]
+pub struct ChangesetFile {
+    pub diff: FileDiff,
+    /// Hunks were dropped to stay within the line budget; fetch them with `get_file_diff`.
+    pub truncated: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "specta", derive(Type))]
+#[serde(rename_all = "camelCase")]
+pub struct ChangesetDiff {
+    pub files: Vec<ChangesetFile>,
+    pub truncated: bool,
+}
+
+/// Line diff algorithm. There is no histogram variant because libgit2 exposes no flag for it;
+/// `Patience` is the closest available choice.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
 #[cfg_attr(feature = "specta", derive(Type))]
 #[serde(rename_all = "lowercase")]
+pub enum DiffAlgorithm {
+    #[default]
+    Myers,
+    Minimal,
+    Patience,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "specta", derive(Type))]
+#[serde(rename_all = "camelCase")]
+pub enum WhitespaceMode {
+    #[default]
+    Show,
+    IgnoreEol,
+    IgnoreChange,
+    IgnoreAll,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "specta", derive(Type))]
+#[serde(rename_all = "camelCase")]
+pub enum RenameDetection {
+    Off,
+    #[default]
+    Renames,
+    RenamesAndCopies,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "specta", derive(Type))]
+#[serde(rename_all = "camelCase", default)]
+pub struct DiffOptions {
+    pub context_lines: u32,
+    pub interhunk_lines: u32,
+    pub whitespace: WhitespaceMode,
+    pub algorithm: DiffAlgorithm,
+    pub indent_heuristic: bool,
+    pub renames: RenameDetection,
+    /// Minimum similarity percentage for a rename or copy to be reported.
+    pub similarity_threshold: u16,
+    pub blame_deletions: bool,
+}
+
+impl Default for DiffOptions {
+    fn default() -> Self {
+        Self {
+            context_lines: 5,
+            interhunk_lines: 0,
+            whitespace: WhitespaceMode::default(),
+            algorithm: DiffAlgorithm::default(),
+            indent_heuristic: false,
+            renames: RenameDetection::default(),
+            similarity_threshold: 50,
+            blame_deletions: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "specta", derive(Type))]
+#[serde(rename_all = "camelCase")]
 pub enum DiffTarget {
     Staged,
     Unstaged,
+    /// Compares HEAD directly against the working tree, including untracked files.
+    Uncommitted,
+    /// Compares two revisions. Without `from`, `to` is compared against its first parent.
+    Range {
+        from: Option<String>,
+        to: String,
+    },
+    /// Compares `head` against its merge-base with `base`, like `git diff base...head`.
+    MergeBase {
+        base: String,
+        head: String,
+    },
+    /// Compares `stash@{index}` against the commit it was created on, including untracked files.
+    Stash {
+        index: u32,
+    },
+}
+
+impl DiffTarget {
+    /// Whether the target reads the index or working tree, which a bare repository lacks.
+    #[must_use]
+    pub fn uses_working_tree(&self) -> bool {
+        matches!(self, Self::Staged | Self::Unstaged | Self::Uncommitted)
+    }
+
+    /// Parses `A..B` and `A...B` ranges; a bare revision means "this commit against its parent".
+    #[must_use]
+    pub fn from_revspec(spec: &str) -> Self {
+        if let Some((base, head)) = spec.split_once("...") {
+            return Self::MergeBase {
+                base: if base.is_empty() { "HEAD" } else { base }.to_owned(),
+                head: if head.is_empty() { "HEAD" } else { head }.to_owned(),
+            };
+        }
+        match spec.split_once("..") {
+            Some((from, to)) => Self::Range {
+                from: Some(if from.is_empty() { "HEAD" } else { from }.to_owned()),
+                to: if to.is_empty() { "HEAD" } else { to }.to_owned(),
+            },
+            None => Self::Range {
+                from: None,
+                to: spec.to_owned(),
+            },
+        }
+    }
+}
+
+/// Commits between a submodule's old and new pointers, like `git diff --submodule=log`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "specta", derive(Type))]
+#[serde(rename_all = "camelCase")]
+pub struct SubmoduleDiff {
+    pub path: String,
+    /// Absolute path of the checked-out submodule, usable as a `repo_path`; `None` if not initialized.
+    pub repo_path: Option<String>,
+    pub old_commit: Option<String>,
+    pub new_commit: Option<String>,
+    pub added: Vec<CommitInfo>,
+    pub removed: Vec<CommitInfo>,
+    /// `added` or `removed` stops at the newest 100 commits.
+    pub truncated: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "specta", derive(Type))]
+#[serde(rename_all = "camelCase")]
+pub struct StashEntry {
+    pub index: u32,
+    pub id: String,
+    pub message: String,
+    pub author: CommitSignature,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "specta", derive(Type))]
+#[serde(rename_all = "camelCase")]
+pub struct ChangeSummary {
+    pub staged: u32,
+    pub unstaged: u32,
+    pub untracked: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "specta", derive(Type))]
+#[serde(rename_all = "camelCase")]
+pub struct WorktreeInfo {
+    /// `None` for the main worktree.
+    pub name: Option<String>,
+    pub path: String,
+    pub is_current: bool,
+    pub locked: bool,
+    /// `None` when the worktree directory is missing or cannot be opened.
+    pub head: Option<HeadState>,
+    pub changes: Option<ChangeSummary>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -114,6 +419,145 @@ pub struct GitFileContents {
     pub new_file: DiffFile,
 }
 
+/// A run of lines in a conflicted working-tree file; line numbers are 1-based and inclusive,
+/// and a `Conflict` spans its marker lines.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "specta", derive(Type))]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum ConflictRegion {
+    #[serde(rename_all = "camelCase")]
+    Common {
+        start_line: u32,
+        end_line: u32,
+        lines: Vec<String>,
+    },
+    #[serde(rename_all = "camelCase")]
+    Conflict {
+        start_line: u32,
+        end_line: u32,
+        ours_label: String,
+        theirs_label: String,
+        ours: Vec<String>,
+        /// Only present when the file was written with diff3-style markers.
+        base: Option<Vec<String>>,
+        theirs: Vec<String>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "specta", derive(Type))]
+#[serde(tag = "choice", rename_all = "camelCase")]
+pub enum ConflictResolution {
+    Ours,
+    Theirs,
+    /// Ours followed by theirs.
+    Both,
+    Custom {
+        lines: Vec<String>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "specta", derive(Type))]
+#[serde(rename_all = "camelCase")]
+pub struct ConflictContents {
+    pub base: DiffFile,
+    pub ours: DiffFile,
+    pub theirs: DiffFile,
+    pub working: DiffFile,
+    pub regions: Vec<ConflictRegion>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "specta", derive(Type))]
+#[serde(rename_all = "camelCase")]
+pub struct CommitSignature {
+    pub name: String,
+    pub email: String,
+    pub time: i64,
+    pub offset_minutes: i32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "specta", derive(Type))]
+#[serde(rename_all = "camelCase")]
+pub struct CommitInfo {
+    pub id: String,
+    pub short_id: String,
+    pub summary: String,
+    pub body: Option<String>,
+    pub author: CommitSignature,
+    pub committer: CommitSignature,
+    pub parents: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "specta", derive(Type))]
+#[serde(rename_all = "camelCase", default)]
+pub struct LogOptions {
+    /// Revision to start walking from; defaults to HEAD.
+    pub start: Option<String>,
+    pub path: Option<String>,
+    /// Case-insensitive substring matched against author name and email.
+    pub author: Option<String>,
+    pub first_parent: bool,
+    pub offset: u32,
+    pub limit: u32,
+}
+
+impl Default for LogOptions {
+    fn default() -> Self {
+        Self {
+            start: None,
+            path: None,
+            author: None,
+            first_parent: false,
+            offset: 0,
+            limit: 100,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "specta", derive(Type))]
+#[serde(rename_all = "camelCase")]
+pub struct LogPage {
+    pub commits: Vec<CommitInfo>,
+    pub has_more: bool,
+}
+
+/// One commit in a file's history; `target` selects that commit's diff for `get_file_diff`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "specta", derive(Type))]
+#[serde(rename_all = "camelCase")]
+pub struct FileHistoryEntry {
+    pub commit: CommitInfo,
+    pub file: FileEntry,
+    pub target: DiffTarget,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "specta", derive(Type))]
+#[serde(rename_all = "camelCase")]
+pub struct FileHistoryPage {
+    pub entries: Vec<FileHistoryEntry>,
+    pub has_more: bool,
+}
+
+/// A working-tree snapshot taken before a discard, restorable with `restore_discard`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "specta", derive(Type))]
+#[serde(rename_all = "camelCase")]
+pub struct DiscardRecord {
+    pub id: String,
+    pub file_path: String,
+    /// Blob holding the discarded contents; `None` if the file did not exist.
+    pub blob_id: Option<String>,
+    /// Whether only some hunks or lines were discarded.
+    pub partial: bool,
+    pub created_at: i64,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(tag = "type", rename_all = "camelCase")]
 #[cfg_attr(feature = "specta", derive(Type))]
@@ -0,0 +1,218 @@
+use crate::error::CoreError;
+use crate::git::{change_summary, discover_repository, head_state};
+use crate::types::WorktreeInfo;
+use git2::{Repository, WorktreeLockStatus};
+use std::path::{Path, PathBuf};
+
+/// Drops trailing separators so paths from libgit2 compare and display consistently.
+fn normalize(path: &Path) -> PathBuf {
+    path.canonicalize()
+        .unwrap_or_else(|_| path.components().collect())
+}
+
+fn worktree_info(
+    repo: Option<&Repository>,
+    name: Option<&str>,
+    path: &Path,
+    current: Option<&Path>,
+    locked: bool,
+) -> Result<WorktreeInfo, CoreError> {
+    let path = normalize(path);
+    let (head, changes) = match repo {
+        Some(repo) => (Some(head_state(repo)?), Some(change_summary(repo)?)),
+        None => (None, None),
+    };
+
+    Ok(WorktreeInfo {
+        name: name.map(str::to_owned),
+        is_current: current.is_some_and(|current| current == path),
+        path: path.to_string_lossy().into_owned(),
+        locked,
+        head,
+        changes,
+    })
+}
+
+/// All worktrees sharing `repo_path`'s repository, main worktree first.
+pub fn list_worktrees(repo_path: &Path) -> Result<Vec<WorktreeInfo>, CoreError> {
+    let repo = discover_repository(repo_path)?;
+    list_worktrees_with_repo(&repo)
+}
+
+pub(crate) fn list_worktrees_with_repo(repo: &Repository) -> Result<Vec<WorktreeInfo>, CoreError> {
+    let current = repo.workdir().map(normalize);
+
+    let main_repo;
+    let main = if repo.is_worktree() {
+        main_repo = Repository::open(repo.commondir())?;
+        &main_repo
+    } else {
+        repo
+    };
+
+    let mut worktrees = Vec::new();
+    if let Some(workdir) = main.workdir() {
+        worktrees.push(worktree_info(
+            Some(main),
+            None,
+            workdir,
+            current.as_deref(),
+            false,
+        )?);
+    }
+
+    for name in main.worktrees()?.iter().flatten() {
+        let worktree = main.find_worktree(name)?;
+        let locked = matches!(worktree.is_locked()?, WorktreeLockStatus::Locked(_));
+        let opened = worktree
+            .validate()
+            .ok()
+            .and_then(|()| Repository::open_from_worktree(&worktree).ok());
+        worktrees.push(worktree_info(
+            opened.as_ref(),
+            Some(name),
+            worktree.path(),
+            current.as_deref(),
+            locked,
+        )?);
+    }
+
+    Ok(worktrees)
+}
+
+/// Canonical root of `worktree_path` if it is a worktree of `repo_path`'s repository with a
+/// readable HEAD, checked without summarizing every worktree.
+pub fn worktree_root(repo_path: &Path, worktree_path: &Path) -> Result<PathBuf, CoreError> {
+    let current = discover_repository(repo_path)?;
+    let root = worktree_path.canonicalize().map_err(|_| {
+        CoreError::InvalidPath(format!(
+            "Worktree directory is missing: {}",
+            worktree_path.display()
+        ))
+    })?;
+    let not_a_worktree = || {
+        CoreError::InvalidPath(format!(
+            "Not a worktree of the open repository: {}",
+            root.display()
+        ))
+    };
+
+    let target = Repository::open(&root).map_err(|_| not_a_worktree())?;
+    if target.workdir().map(normalize).as_deref() != Some(root.as_path())
+        || normalize(target.commondir()) != normalize(current.commondir())
+    {
+        return Err(not_a_worktree());
+    }
+    head_state(&target)?;
+    Ok(root)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{commit_file, init_test_repo};
+    use crate::types::{ChangeSummary, HeadState};
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn create_test_repo() -> (TempDir, Repository) {
+        let temp_dir = TempDir::new().unwrap();
+        let repo = init_test_repo(&temp_dir.path().join("main"));
+        (temp_dir, repo)
+    }
+
+    #[test]
+    fn test_list_worktrees_from_linked_worktree() {
+        let (temp_dir, repo) = create_test_repo();
+        commit_file(&repo, "file.txt", "one\n", "Initial");
+
+        let linked_path = temp_dir.path().join("feature");
+        repo.worktree("feature", &linked_path, None).unwrap();
+        fs::write(linked_path.join("file.txt"), "two\n").unwrap();
+        fs::write(linked_path.join("new.txt"), "new\n").unwrap();
+
+        let worktrees = list_worktrees(&linked_path).unwrap();
+        assert_eq!(worktrees.len(), 2);
+
+        let main = &worktrees[0];
+        assert_eq!(main.name, None);
+        assert!(!main.is_current);
+        assert!(matches!(main.head, Some(HeadState::Branch { .. })));
+        assert_eq!(
+            main.changes,
+            Some(ChangeSummary {
+                staged: 0,
+                unstaged: 0,
+                untracked: 0,
+            })
+        );
+
+        let linked = &worktrees[1];
+        assert_eq!(linked.name.as_deref(), Some("feature"));
+        assert!(linked.is_current);
+        assert_eq!(
+            PathBuf::from(&linked.path),
+            linked_path.canonicalize().unwrap()
+        );
+        assert_eq!(
+            linked.head,
+            Some(HeadState::Branch {
+                name: "feature".to_owned(),
+            })
+        );
+        assert_eq!(
+            linked.changes,
+            Some(ChangeSummary {
+                staged: 0,
+                unstaged: 1,
+                untracked: 1,
+            })
+        );
+    }
+
+    #[test]
+    fn test_list_worktrees_reports_locked_and_missing() {
+        let (temp_dir, repo) = create_test_repo();
+        commit_file(&repo, "file.txt", "one\n", "Initial");
+
+        let linked_path = temp_dir.path().join("gone");
+        let worktree = repo.worktree("gone", &linked_path, None).unwrap();
+        worktree.lock(Some("offline")).unwrap();
+        fs::remove_dir_all(&linked_path).unwrap();
+
+        let worktrees = list_worktrees(repo.workdir().unwrap()).unwrap();
+        assert!(worktrees[0].is_current);
+
+        let gone = &worktrees[1];
+        assert!(gone.locked);
+        assert_eq!(gone.head, None);
+        assert_eq!(gone.changes, None);
+    }
+
+    #[test]
+    fn test_worktree_root_accepts_only_sibling_worktrees() {
+        let (temp_dir, repo) = create_test_repo();
+        commit_file(&repo, "file.txt", "one\n", "Initial");
+        let linked_path = temp_dir.path().join("feature");
+        repo.worktree("feature", &linked_path, None).unwrap();
+        let main_path = repo.workdir().unwrap();
+
+        let root = worktree_root(main_path, &linked_path.join("..").join("feature")).unwrap();
+        assert_eq!(root, linked_path.canonicalize().unwrap());
+        assert_eq!(
+            worktree_root(&linked_path, main_path).unwrap(),
+            main_path.canonicalize().unwrap()
+        );
+
+        fs::create_dir(linked_path.join("sub")).unwrap();
+        let (other_dir, _other) = create_test_repo();
+        for path in [
+            linked_path.join("sub"),
+            other_dir.path().join("main"),
+            temp_dir.path().join("missing"),
+        ] {
+            let result = worktree_root(main_path, &path);
+            assert!(matches!(result, Err(CoreError::InvalidPath(_))));
+        }
+    }
+}
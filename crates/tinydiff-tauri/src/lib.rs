@@ -4,10 +4,13 @@ use clap::{CommandFactory, Parser, error::ErrorKind};
 use serde::{Deserialize, Serialize};
 use specta::Type;
 use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
 use thiserror::Error;
 use tinydiff_core::{
-    Comment, CommentCollection, CoreError, DiffTarget, FileDiff, GitFileContents, GitStatus,
-    ReadFileResult,
+    ChangesetDiff, Comment, CommentCollection, CommitInfo, ConflictContents, ConflictResolution,
+    CoreError, DiffLine, DiffOptions, DiffSide, DiffTarget, DiscardRecord, FileDiff, FileEntry,
+    FileHistoryPage, GitFileContents, GitStatus, HunkSelection, LineBlame, LogOptions, LogPage,
+    PatchAction, ReadFileResult, RepoSession, StashEntry, SubmoduleDiff, WorktreeInfo,
 };
 
 #[derive(Parser)]
@@ -63,6 +66,14 @@ pub enum CommandError {
     InvalidUtf8 { path: String },
     #[serde(rename = "git")]
     Git { path: String, message: String },
+    #[serde(rename = "stale")]
+    Stale { path: String, message: String },
+    #[serde(rename = "hook")]
+    Hook {
+        hook: String,
+        exit_code: Option<i32>,
+        output: String,
+    },
 }
 
 impl From<AppError> for CommandError {
@@ -98,7 +109,24 @@ impl From<CoreError> for CommandError {
                 path: String::new(),
                 message: msg.clone(),
             },
-            CoreError::Git(_) | CoreError::TaskPanic(_) => CommandError::Git {
+            CoreError::StaleSelection(path) => CommandError::Stale {
+                path: path.clone(),
+                message: err.to_string(),
+            },
+            CoreError::Hook {
+                hook,
+                exit_code,
+                output,
+            } => CommandError::Hook {
+                hook: hook.clone(),
+                exit_code: *exit_code,
+                output: output.clone(),
+            },
+            CoreError::Git(_)
+            | CoreError::TaskPanic(_)
+            | CoreError::NothingToCommit
+            | CoreError::EmptyCommitMessage
+            | CoreError::BareRepository => CommandError::Git {
                 path: String::new(),
                 message: err.to_string(),
             },
@@ -145,15 +173,179 @@ pub fn parse_app_mode_from_args(args: Args) -> Result<AppMode, AppError> {
 
 #[tauri::command]
 #[specta::specta]
-fn get_app_mode(state: tauri::State<'_, AppMode>) -> AppMode {
-    state.inner().clone()
+fn get_app_mode(state: tauri::State<'_, Mutex<AppMode>>) -> AppMode {
+    lock_app_mode(&state).clone()
+}
+
+fn lock_app_mode(state: &Mutex<AppMode>) -> MutexGuard<'_, AppMode> {
+    state.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+const MAX_SESSIONS: usize = 8;
+
+type SharedSession = Arc<Mutex<RepoSession>>;
+
+/// Open repositories keyed by canonical path, most recently used last, so their caches last
+/// across commands. The commands using them are synchronous, which Tauri runs one at a time on
+/// the main thread, so commands for different repositories still wait on each other.
+#[derive(Default)]
+pub struct RepoSessions(Mutex<Vec<(PathBuf, SharedSession)>>);
+
+impl RepoSessions {
+    fn get(&self, repo_path: &Path) -> Result<SharedSession, CoreError> {
+        let key = std::fs::canonicalize(repo_path).map_err(|e| CoreError::io(repo_path, e))?;
+        if let Some(session) = self.touch(&key) {
+            return Ok(session);
+        }
+
+        let opened = Arc::new(Mutex::new(RepoSession::open(&key)?));
+        if let Some(session) = self.touch(&key) {
+            return Ok(session);
+        }
+        let mut sessions = self.0.lock().unwrap_or_else(PoisonError::into_inner);
+        if sessions.len() >= MAX_SESSIONS {
+            sessions.remove(0);
+        }
+        sessions.push((key, Arc::clone(&opened)));
+        Ok(opened)
+    }
+
+    /// The session for `key`, moved to the most recently used end.
+    fn touch(&self, key: &Path) -> Option<SharedSession> {
+        let mut sessions = self.0.lock().unwrap_or_else(PoisonError::into_inner);
+        let position = sessions.iter().position(|(path, _)| path == key)?;
+        let entry = sessions.remove(position);
+        let session = Arc::clone(&entry.1);
+        sessions.push(entry);
+        Some(session)
+    }
+}
+
+fn with_session<T>(
+    sessions: &RepoSessions,
+    repo_path: String,
+    f: impl FnOnce(&mut RepoSession) -> Result<T, CoreError>,
+) -> Result<T, CommandError> {
+    let session = sessions.get(Path::new(&repo_path))?;
+    let mut session = session.lock().unwrap_or_else(PoisonError::into_inner);
+    Ok(f(&mut *session)?)
+}
+
+#[tauri::command]
+#[specta::specta]
+fn get_git_status(
+    path: String,
+    include_stats: bool,
+    sessions: tauri::State<'_, RepoSessions>,
+) -> Result<GitStatus, CommandError> {
+    if include_stats {
+        with_session(&sessions, path, RepoSession::status_with_stats)
+    } else {
+        with_session(&sessions, path, RepoSession::status)
+    }
+}
+
+#[tauri::command]
+#[specta::specta]
+fn create_commit(
+    repo_path: String,
+    message: String,
+    amend: bool,
+    sessions: tauri::State<'_, RepoSessions>,
+) -> Result<CommitInfo, CommandError> {
+    with_session(&sessions, repo_path, |session| {
+        session.create_commit(&message, amend)
+    })
+}
+
+#[tauri::command]
+#[specta::specta]
+fn stage_file(
+    repo_path: String,
+    file_path: String,
+    sessions: tauri::State<'_, RepoSessions>,
+) -> Result<GitStatus, CommandError> {
+    with_session(&sessions, repo_path, |session| {
+        session.stage_file(&file_path)
+    })
+}
+
+#[tauri::command]
+#[specta::specta]
+fn unstage_file(
+    repo_path: String,
+    file_path: String,
+    sessions: tauri::State<'_, RepoSessions>,
+) -> Result<GitStatus, CommandError> {
+    with_session(&sessions, repo_path, |session| {
+        session.unstage_file(&file_path)
+    })
 }
 
 #[tauri::command]
 #[specta::specta]
-fn get_git_status(path: String) -> Result<GitStatus, CommandError> {
-    let path_buf = PathBuf::from(&path);
-    Ok(tinydiff_core::get_status(&path_buf)?)
+fn discard_file(
+    repo_path: String,
+    file_path: String,
+    sessions: tauri::State<'_, RepoSessions>,
+) -> Result<DiscardRecord, CommandError> {
+    with_session(&sessions, repo_path, |session| {
+        session.discard_file(&file_path)
+    })
+}
+
+#[tauri::command]
+#[specta::specta]
+fn list_discards(
+    repo_path: String,
+    sessions: tauri::State<'_, RepoSessions>,
+) -> Result<Vec<DiscardRecord>, CommandError> {
+    with_session(&sessions, repo_path, RepoSession::list_discards)
+}
+
+#[tauri::command]
+#[specta::specta]
+fn restore_discard(
+    repo_path: String,
+    discard_id: String,
+    sessions: tauri::State<'_, RepoSessions>,
+) -> Result<GitStatus, CommandError> {
+    with_session(&sessions, repo_path, |session| {
+        session.restore_discard(&discard_id)
+    })
+}
+
+#[tauri::command]
+#[specta::specta]
+fn apply_selection(
+    repo_path: String,
+    file_path: String,
+    action: PatchAction,
+    selections: Vec<HunkSelection>,
+    options: Option<DiffOptions>,
+    sessions: tauri::State<'_, RepoSessions>,
+) -> Result<GitStatus, CommandError> {
+    with_session(&sessions, repo_path, |session| {
+        session.apply_selection(
+            &file_path,
+            action,
+            &selections,
+            &options.unwrap_or_default(),
+        )
+    })
+}
+
+#[tauri::command]
+#[specta::specta]
+fn get_changed_files(
+    repo_path: String,
+    target: DiffTarget,
+    options: Option<DiffOptions>,
+    sessions: tauri::State<'_, RepoSessions>,
+) -> Result<Vec<FileEntry>, CommandError> {
+    with_session(&sessions, repo_path, |session| {
+        session.changed_files(&target, &options.unwrap_or_default())
+    })
 }
 
 #[tauri::command]
@@ -162,6 +354,8 @@ fn get_file_diff(
     repo_path: String,
     file_path: String,
     target: DiffTarget,
+    options: Option<DiffOptions>,
+    sessions: tauri::State<'_, RepoSessions>,
 ) -> Result<FileDiff, CommandError> {
     if file_path.contains("..") || Path::new(&file_path).is_absolute() {
         return Err(CommandError::Git {
@@ -169,8 +363,84 @@ fn get_file_diff(
             message: "Invalid file path".to_owned(),
         });
     }
-    let path_buf = PathBuf::from(&repo_path);
-    Ok(tinydiff_core::get_file_diff(&path_buf, &file_path, target)?)
+    with_session(&sessions, repo_path, |session| {
+        session.file_diff(&file_path, &target, &options.unwrap_or_default())
+    })
+}
+
+#[tauri::command]
+#[specta::specta]
+fn get_changeset_diff(
+    repo_path: String,
+    target: DiffTarget,
+    options: Option<DiffOptions>,
+    max_lines: Option<u32>,
+    sessions: tauri::State<'_, RepoSessions>,
+) -> Result<ChangesetDiff, CommandError> {
+    with_session(&sessions, repo_path, |session| {
+        session.changeset_diff(&target, &options.unwrap_or_default(), max_lines)
+    })
+}
+
+#[tauri::command]
+#[specta::specta]
+fn get_conflict_contents(
+    repo_path: String,
+    file_path: String,
+    sessions: tauri::State<'_, RepoSessions>,
+) -> Result<ConflictContents, CommandError> {
+    with_session(&sessions, repo_path, |session| {
+        session.conflict_contents(&file_path)
+    })
+}
+
+#[tauri::command]
+#[specta::specta]
+fn resolve_conflicts(
+    repo_path: String,
+    file_path: String,
+    resolutions: Vec<ConflictResolution>,
+    sessions: tauri::State<'_, RepoSessions>,
+) -> Result<GitStatus, CommandError> {
+    with_session(&sessions, repo_path, |session| {
+        session.resolve_conflicts(&file_path, &resolutions)
+    })
+}
+
+#[tauri::command]
+#[specta::specta]
+fn mark_resolved(
+    repo_path: String,
+    file_path: String,
+    sessions: tauri::State<'_, RepoSessions>,
+) -> Result<GitStatus, CommandError> {
+    with_session(&sessions, repo_path, |session| {
+        session.mark_resolved(&file_path)
+    })
+}
+
+#[tauri::command]
+#[specta::specta]
+fn get_context_lines(
+    repo_path: String,
+    file_path: String,
+    target: DiffTarget,
+    side: DiffSide,
+    start: u32,
+    end: u32,
+    options: Option<DiffOptions>,
+    sessions: tauri::State<'_, RepoSessions>,
+) -> Result<Vec<DiffLine>, CommandError> {
+    with_session(&sessions, repo_path, |session| {
+        session.context_lines(
+            &file_path,
+            &target,
+            side,
+            start,
+            end,
+            &options.unwrap_or_default(),
+        )
+    })
 }
 
 #[tauri::command]
@@ -179,20 +449,156 @@ fn get_git_file_contents(
     repo_path: String,
     file_path: String,
     target: DiffTarget,
+    sessions: tauri::State<'_, RepoSessions>,
 ) -> Result<GitFileContents, CommandError> {
-    let path_buf = PathBuf::from(&repo_path);
-    Ok(tinydiff_core::get_git_file_contents(
-        &path_buf, &file_path, target,
-    )?)
+    with_session(&sessions, repo_path, |session| {
+        session.git_file_contents(&file_path, &target)
+    })
+}
+
+#[tauri::command]
+#[specta::specta]
+fn get_commit_log(
+    repo_path: String,
+    options: LogOptions,
+    sessions: tauri::State<'_, RepoSessions>,
+) -> Result<LogPage, CommandError> {
+    with_session(&sessions, repo_path, |session| session.log(&options))
+}
+
+#[tauri::command]
+#[specta::specta]
+fn get_file_history(
+    repo_path: String,
+    file_path: String,
+    options: LogOptions,
+    sessions: tauri::State<'_, RepoSessions>,
+) -> Result<FileHistoryPage, CommandError> {
+    with_session(&sessions, repo_path, |session| {
+        session.file_history(&file_path, &options)
+    })
+}
+
+#[tauri::command]
+#[specta::specta]
+fn blame_file(
+    repo_path: String,
+    file_path: String,
+    rev: Option<String>,
+    sessions: tauri::State<'_, RepoSessions>,
+) -> Result<Vec<LineBlame>, CommandError> {
+    with_session(&sessions, repo_path, |session| {
+        session.blame(&file_path, rev.as_deref())
+    })
+}
+
+#[tauri::command]
+#[specta::specta]
+fn list_stashes(
+    repo_path: String,
+    sessions: tauri::State<'_, RepoSessions>,
+) -> Result<Vec<StashEntry>, CommandError> {
+    with_session(&sessions, repo_path, RepoSession::list_stashes)
+}
+
+#[tauri::command]
+#[specta::specta]
+fn create_stash(
+    repo_path: String,
+    message: Option<String>,
+    include_untracked: bool,
+    sessions: tauri::State<'_, RepoSessions>,
+) -> Result<StashEntry, CommandError> {
+    with_session(&sessions, repo_path, |session| {
+        session.create_stash(message.as_deref(), include_untracked)
+    })
+}
+
+#[tauri::command]
+#[specta::specta]
+fn apply_stash(
+    repo_path: String,
+    index: u32,
+    sessions: tauri::State<'_, RepoSessions>,
+) -> Result<GitStatus, CommandError> {
+    with_session(&sessions, repo_path, |session| session.apply_stash(index))
+}
+
+#[tauri::command]
+#[specta::specta]
+fn pop_stash(
+    repo_path: String,
+    index: u32,
+    sessions: tauri::State<'_, RepoSessions>,
+) -> Result<GitStatus, CommandError> {
+    with_session(&sessions, repo_path, |session| session.pop_stash(index))
+}
+
+#[tauri::command]
+#[specta::specta]
+fn drop_stash(
+    repo_path: String,
+    index: u32,
+    sessions: tauri::State<'_, RepoSessions>,
+) -> Result<Vec<StashEntry>, CommandError> {
+    with_session(&sessions, repo_path, |session| session.drop_stash(index))
+}
+
+#[tauri::command]
+#[specta::specta]
+fn get_submodule_diff(
+    repo_path: String,
+    submodule_path: String,
+    target: DiffTarget,
+    sessions: tauri::State<'_, RepoSessions>,
+) -> Result<SubmoduleDiff, CommandError> {
+    with_session(&sessions, repo_path, |session| {
+        session.submodule_diff(&submodule_path, &target)
+    })
+}
+
+#[tauri::command]
+#[specta::specta]
+fn list_worktrees(
+    repo_path: String,
+    sessions: tauri::State<'_, RepoSessions>,
+) -> Result<Vec<WorktreeInfo>, CommandError> {
+    with_session(&sessions, repo_path, RepoSession::worktrees)
+}
+
+/// Points the window at another worktree of the repository currently open.
+#[tauri::command]
+#[specta::specta]
+fn switch_worktree(
+    worktree_path: String,
+    state: tauri::State<'_, Mutex<AppMode>>,
+) -> Result<AppMode, CommandError> {
+    let AppMode::Git { path } = lock_app_mode(&state).clone() else {
+        return Err(CommandError::Path {
+            path: worktree_path,
+            message: "switch_worktree is only available in git mode".to_owned(),
+        });
+    };
+
+    let root = tinydiff_core::worktree_root(&PathBuf::from(path), &PathBuf::from(&worktree_path))?;
+    let mode = AppMode::Git {
+        path: path_to_string(&root).map_err(|e| CommandError::Path {
+            path: worktree_path,
+            message: e.to_string(),
+        })?,
+    };
+    *lock_app_mode(&state) = mode.clone();
+    Ok(mode)
 }
 
 #[tauri::command]
 #[specta::specta]
 fn read_file(
     file_path: String,
-    state: tauri::State<'_, AppMode>,
+    state: tauri::State<'_, Mutex<AppMode>>,
 ) -> Result<ReadFileResult, CommandError> {
-    let (allowed_a, allowed_b) = match state.inner() {
+    let mode = lock_app_mode(&state);
+    let (allowed_a, allowed_b) = match &*mode {
         AppMode::File { file_a, file_b } => (file_a.as_str(), file_b.as_str()),
         _ => {
             return Err(CommandError::Path {
@@ -288,8 +694,32 @@ pub fn run() {
         tauri_specta::Builder::<tauri::Wry>::new().commands(tauri_specta::collect_commands![
             get_app_mode,
             get_git_status,
+            create_commit,
+            get_changed_files,
             get_file_diff,
+            get_changeset_diff,
+            get_context_lines,
+            get_conflict_contents,
+            resolve_conflicts,
+            mark_resolved,
             get_git_file_contents,
+            get_commit_log,
+            get_file_history,
+            blame_file,
+            stage_file,
+            unstage_file,
+            apply_selection,
+            discard_file,
+            list_discards,
+            restore_discard,
+            list_stashes,
+            create_stash,
+            apply_stash,
+            pop_stash,
+            drop_stash,
+            get_submodule_diff,
+            list_worktrees,
+            switch_worktree,
             read_file,
             load_comments,
             save_comment,
@@ -311,7 +741,8 @@ pub fn run() {
     }
 
     tauri::Builder::default()
-        .manage(app_mode)
+        .manage(Mutex::new(app_mode))
+        .manage(RepoSessions::default())
         .plugin(tauri_plugin_window_state::Builder::new().build())
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_store::Builder::default().build())
@@ -1,22 +1,41 @@
-import type { GitStatus } from '#tauri-bindings/index';
+import type { FileEntry, FileEntryKind, GitStatus } from '#tauri-bindings/index';
+
+function entry(path: string, kind: FileEntryKind): FileEntry {
+  return { path, kind, stats: null };
+}
+
+const staged: FileEntry[] = [
+  entry('src/features/diff-viewer/diff-viewer.tsx', { status: 'modified' }),
+  entry('src/features/comments/comment-components.tsx', { status: 'modified' }),
+  entry('src/lib/settings-store.ts', { status: 'added' }),
+  entry('src-tauri/src/comments.rs', { status: 'modified' }),
+  entry('src/utils/old-helpers.ts', {
+    status: 'renamed',
+    old_path: 'src/utils/helpers.ts',
+    similarity: 92
+  })
+];
+
+const unstaged: FileEntry[] = [
+  entry('src/app.tsx', { status: 'modified' }),
+  entry('src/styles/main.css', { status: 'modified' }),
+  entry('README.md', { status: 'modified' }),
+  entry('package.json', { status: 'modified' })
+];
+
+const untracked: FileEntry[] = [
+  entry('src/features/dashboard/index.tsx', { status: 'untracked' }),
+  entry('src/features/dashboard/Dashboard.tsx', { status: 'untracked' }),
+  entry('.env.local', { status: 'untracked' })
+];
 
 export const mockGitStatus: GitStatus = {
-  staged: [
-    { path: 'src/features/diff-viewer/diff-viewer.tsx', status: 'modified', oldPath: null },
-    { path: 'src/features/comments/comment-components.tsx', status: 'modified', oldPath: null },
-    { path: 'src/lib/settings-store.ts', status: 'added', oldPath: null },
-    { path: 'src-tauri/src/comments.rs', status: 'modified', oldPath: null },
-    { path: 'src/utils/old-helpers.ts', status: 'renamed', oldPath: 'src/utils/helpers.ts' }
-  ],
-  unstaged: [
-    { path: 'src/app.tsx', status: 'modified', oldPath: null },
-    { path: 'src/styles/main.css', status: 'modified', oldPath: null },
-    { path: 'README.md', status: 'modified', oldPath: null },
-    { path: 'package.json', status: 'modified', oldPath: null }
-  ],
-  untracked: [
-    { path: 'src/features/dashboard/index.tsx', status: 'untracked', oldPath: null },
-    { path: 'src/features/dashboard/Dashboard.tsx', status: 'untracked', oldPath: null },
-    { path: '.env.local', status: 'untracked', oldPath: null }
-  ]
+  bare: false,
+  head: { type: 'branch', name: 'feature/comments' },
+  upstream: { name: 'origin/feature/comments', ahead: 2, behind: 0 },
+  operation: null,
+  staged,
+  unstaged,
+  untracked,
+  uncommitted: [...staged, ...unstaged, ...untracked]
 };
@@ -4,6 +4,7 @@ import type {
   CommentCollection,
   CommandError,
   DiffTarget,
+  FileDiff,
   GitFileContents,
   GitStatus
 } from '#tauri-bindings/index';
@@ -61,7 +62,7 @@ async function handleGetAppMode(): Promise<AppMode> {
   return { type: 'git', path: MOCK_REPO_PATH };
 }
 
-async function handleGetGitStatus(_path: string): Promise<GitStatus> {
+async function handleGetGitStatus(_path: string, _includeStats: boolean): Promise<GitStatus> {
   await delay(100);
   return mockGitStatus;
 }
@@ -70,7 +71,7 @@ async function handleGetFileDiff(
   _repoPath: string,
   filePath: string,
   _target: DiffTarget
-): Promise<unknown> {
+): Promise<FileDiff> {
   await delay(50);
 
   const contents = fileContentsMap[filePath] || binaryFiles[filePath];
@@ -78,12 +79,13 @@ async function handleGetFileDiff(
     throwError({ type: 'path', path: filePath, message: 'File not found in fixtures' });
   }
 
+  const isBinary =
+    contents.oldFile.content?.type === 'binary' || contents.newFile.content?.type === 'binary';
   return {
     path: filePath,
     oldPath: null,
-    hunks: [],
-    isBinary:
-      contents.oldFile.content?.type === 'binary' || contents.newFile.content?.type === 'binary'
+    similarity: null,
+    content: isBinary ? { contentType: 'binary' } : { contentType: 'text', hunks: [] }
   };
 }
 
@@ -208,7 +210,7 @@ async function mockInvoke(cmd: string, args?: InvokeArgs): Promise<unknown> {
       return handleGetAppMode();
 
     case 'get_git_status':
-      return handleGetGitStatus(args?.path as string);
+      return handleGetGitStatus(args?.path as string, args?.includeStats as boolean);
 
     case 'get_file_diff':
       return handleGetFileDiff(
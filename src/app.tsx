@@ -46,7 +46,10 @@ function getErrorMessage(error: CommandError): string {
     case 'utf8':
       return `UTF-8 encoding error for ${error.path}`;
     case 'git':
+    case 'stale':
       return error.message;
+    case 'hook':
+      return `${error.hook} hook failed: ${error.output}`;
   }
 }
 
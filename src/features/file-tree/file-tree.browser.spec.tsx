@@ -9,13 +9,18 @@ import { FileTree } from './file-tree';
 
 function createStatus(files: { path: string; staged?: boolean }[]): GitStatus {
   return {
+    bare: false,
+    head: { type: 'branch', name: 'main' },
+    upstream: null,
+    operation: null,
     staged: files
       .filter((f) => f.staged === true)
-      .map((f) => ({ path: f.path, kind: { status: 'modified' as const } })),
+      .map((f) => ({ path: f.path, kind: { status: 'modified' as const }, stats: null })),
     unstaged: files
       .filter((f) => f.staged !== true)
-      .map((f) => ({ path: f.path, kind: { status: 'modified' as const } })),
-    untracked: []
+      .map((f) => ({ path: f.path, kind: { status: 'modified' as const }, stats: null })),
+    untracked: [],
+    uncommitted: []
   };
 }
 
@@ -71,7 +76,7 @@ describe('FileTree keyboard navigation', () => {
   });
 
   it('shows "No changes detected" when tree is empty', async () => {
-    const status: GitStatus = { staged: [], unstaged: [], untracked: [] };
+    const status = createStatus([]);
     const onSelectFile = vi.fn();
 
     const screen = await renderFileTree({ status, selectedFile: null, onSelectFile });
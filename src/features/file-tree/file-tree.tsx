@@ -21,7 +21,12 @@ import { useRef } from 'react';
 
 import type { GitStatus, DiffTarget } from '../../../tauri-bindings';
 
-import { buildFileTree, getStatusLabel, type FileTreeNode } from './tree-builder';
+import {
+  buildFileTree,
+  getStatusColorName,
+  getStatusLabel,
+  type FileTreeNode
+} from './tree-builder';
 import { useFileTreeKeyboard } from './use-file-tree-keyboard';
 
 interface FileTreeProps {
@@ -129,9 +134,7 @@ function TreeItem({
           <span className="flex-1 truncate text-sm">{node.name}</span>
           <span
             className="ml-2 shrink-0 text-2xs font-semibold"
-            style={{
-              color: `var(--git-${node.kind.status === 'typechange' ? 'renamed' : node.kind.status})`
-            }}
+            style={{ color: `var(--git-${getStatusColorName(node.kind.status)})` }}
           >
             {getStatusLabel(node.kind.status)}
           </span>
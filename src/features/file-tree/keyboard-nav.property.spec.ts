@@ -42,12 +42,18 @@ const keySequenceArb = fc.array(navKeyArb, { minLength: 1, maxLength: 20 });
 
 function createGitStatus(paths: string[]): GitStatus {
   return {
+    bare: false,
+    head: { type: 'branch', name: 'main' },
+    upstream: null,
+    operation: null,
     staged: [],
     unstaged: paths.map((path) => ({
       path,
-      kind: { status: 'modified' as const }
+      kind: { status: 'modified' as const },
+      stats: null
     })),
-    untracked: []
+    untracked: [],
+    uncommitted: []
   };
 }
 
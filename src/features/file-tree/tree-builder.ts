@@ -95,11 +95,17 @@ const STATUS_META = {
   modified: { label: 'M', className: 'text-git-modified', colorName: 'modified' },
   deleted: { label: 'D', className: 'text-git-deleted', colorName: 'deleted' },
   renamed: { label: 'R', className: 'text-git-renamed', colorName: 'renamed' },
+  copied: { label: 'Cp', className: 'text-git-renamed', colorName: 'renamed' },
   untracked: { label: 'U', className: 'text-git-untracked', colorName: 'untracked' },
   typechange: { label: 'T', className: 'text-git-renamed', colorName: 'renamed' },
-  conflicted: { label: 'C', className: 'text-git-conflicted', colorName: 'conflicted' }
+  conflicted: { label: 'C', className: 'text-git-conflicted', colorName: 'conflicted' },
+  submodule: { label: 'S', className: 'text-git-modified', colorName: 'modified' }
 } satisfies Record<FileStatus, { label: string; className: string; colorName: string }>;
 
 export function getStatusLabel(status: FileStatus): string {
   return STATUS_META[status].label;
 }
+
+export function getStatusColorName(status: FileStatus): string {
+  return STATUS_META[status].colorName;
+}
@@ -12,7 +12,7 @@ export function useGitStatus(repoPath: string) {
 
   const refresh = useCallback(async () => {
     setState({ status: 'loading' });
-    const result = await commands.getGitStatus(repoPath);
+    const result = await commands.getGitStatus(repoPath, false);
     if (result.status === 'ok') {
       setState({ status: 'success', data: result.data });
     } else {
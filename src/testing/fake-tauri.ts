@@ -1,4 +1,11 @@
-import type { CommandError, GitFileContents, GitStatus, Result } from '#tauri-bindings/index';
+import type {
+  CommandError,
+  DiffTarget,
+  FileEntry,
+  GitFileContents,
+  GitStatus,
+  Result
+} from '#tauri-bindings/index';
 
 type FakeResult<T> = Result<T, CommandError>;
 
@@ -7,7 +14,7 @@ export function createFakeTauri() {
     gitStatus: null as FakeResult<GitStatus> | null,
     gitFileContents: new Map<string, FakeResult<GitFileContents>>(),
 
-    getGitStatus(_path: string): FakeResult<GitStatus> {
+    getGitStatus(_path: string, _includeStats = false): FakeResult<GitStatus> {
       if (this.gitStatus) return this.gitStatus;
       return { status: 'error', error: { type: 'git', path: '', message: 'not configured' } };
     },
@@ -15,7 +22,7 @@ export function createFakeTauri() {
     getGitFileContents(
       _repoPath: string,
       filePath: string,
-      _target: string
+      _target: DiffTarget
     ): FakeResult<GitFileContents> {
       const result = this.gitFileContents.get(filePath);
       if (result) return result;
@@ -28,9 +35,14 @@ export type FakeTauri = ReturnType<typeof createFakeTauri>;
 
 export function createMockGitStatus(overrides: Partial<GitStatus> = {}): GitStatus {
   return {
+    bare: false,
+    head: { type: 'branch', name: 'main' },
+    upstream: null,
+    operation: null,
     staged: [],
     unstaged: [],
     untracked: [],
+    uncommitted: [],
     ...overrides
   };
 }
@@ -38,8 +50,8 @@ export function createMockGitStatus(overrides: Partial<GitStatus> = {}): GitStat
 export function createMockFileEntry(
   path: string,
   status: 'added' | 'modified' | 'deleted' = 'modified'
-) {
-  return { path, status, oldPath: null };
+): FileEntry {
+  return { path, kind: { status }, stats: null };
 }
 
 export function createMockGitFileContents(
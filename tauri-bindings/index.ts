@@ -8,17 +8,73 @@ export const commands = {
 async getAppMode() : Promise<AppMode> {
     return await TAURI_INVOKE("get_app_mode");
 },
-async getGitStatus(path: string) : Promise<Result<GitStatus, CommandError>> {
+async getGitStatus(path: string, includeStats: boolean) : Promise<Result<GitStatus, CommandError>> {
     try {
-    return { status: "ok", data: await TAURI_INVOKE("get_git_status", { path }) };
+    return { status: "ok", data: await TAURI_INVOKE("get_git_status", { path, includeStats }) };
 } catch (e) {
     if(e instanceof Error) throw e;
     else return { status: "error", error: e  as any };
 }
 },
-async getFileDiff(repoPath: string, filePath: string, target: DiffTarget) : Promise<Result<FileDiff, CommandError>> {
+async createCommit(repoPath: string, message: string, amend: boolean) : Promise<Result<CommitInfo, CommandError>> {
     try {
-    return { status: "ok", data: await TAURI_INVOKE("get_file_diff", { repoPath, filePath, target }) };
+    return { status: "ok", data: await TAURI_INVOKE("create_commit", { repoPath, message, amend }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getChangedFiles(repoPath: string, target: DiffTarget, options: DiffOptions | null) : Promise<Result<FileEntry[], CommandError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_changed_files", { repoPath, target, options }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getFileDiff(repoPath: string, filePath: string, target: DiffTarget, options: DiffOptions | null) : Promise<Result<FileDiff, CommandError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_file_diff", { repoPath, filePath, target, options }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getChangesetDiff(repoPath: string, target: DiffTarget, options: DiffOptions | null, maxLines: number | null) : Promise<Result<ChangesetDiff, CommandError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_changeset_diff", { repoPath, target, options, maxLines }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getContextLines(repoPath: string, filePath: string, target: DiffTarget, side: DiffSide, start: number, end: number, options: DiffOptions | null) : Promise<Result<DiffLine[], CommandError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_context_lines", { repoPath, filePath, target, side, start, end, options }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getConflictContents(repoPath: string, filePath: string) : Promise<Result<ConflictContents, CommandError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_conflict_contents", { repoPath, filePath }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async resolveConflicts(repoPath: string, filePath: string, resolutions: ConflictResolution[]) : Promise<Result<GitStatus, CommandError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("resolve_conflicts", { repoPath, filePath, resolutions }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async markResolved(repoPath: string, filePath: string) : Promise<Result<GitStatus, CommandError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("mark_resolved", { repoPath, filePath }) };
 } catch (e) {
     if(e instanceof Error) throw e;
     else return { status: "error", error: e  as any };
@@ -32,6 +88,145 @@ async getGitFileContents(repoPath: string, filePath: string, target: DiffTarget)
     else return { status: "error", error: e  as any };
 }
 },
+async getCommitLog(repoPath: string, options: LogOptions) : Promise<Result<LogPage, CommandError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_commit_log", { repoPath, options }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getFileHistory(repoPath: string, filePath: string, options: LogOptions) : Promise<Result<FileHistoryPage, CommandError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_file_history", { repoPath, filePath, options }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async blameFile(repoPath: string, filePath: string, rev: string | null) : Promise<Result<LineBlame[], CommandError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("blame_file", { repoPath, filePath, rev }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async stageFile(repoPath: string, filePath: string) : Promise<Result<GitStatus, CommandError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("stage_file", { repoPath, filePath }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async unstageFile(repoPath: string, filePath: string) : Promise<Result<GitStatus, CommandError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("unstage_file", { repoPath, filePath }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async applySelection(repoPath: string, filePath: string, action: PatchAction, selections: HunkSelection[], options: DiffOptions | null) : Promise<Result<GitStatus, CommandError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("apply_selection", { repoPath, filePath, action, selections, options }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async discardFile(repoPath: string, filePath: string) : Promise<Result<DiscardRecord, CommandError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("discard_file", { repoPath, filePath }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async listDiscards(repoPath: string) : Promise<Result<DiscardRecord[], CommandError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("list_discards", { repoPath }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async restoreDiscard(repoPath: string, discardId: string) : Promise<Result<GitStatus, CommandError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("restore_discard", { repoPath, discardId }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async listStashes(repoPath: string) : Promise<Result<StashEntry[], CommandError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("list_stashes", { repoPath }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async createStash(repoPath: string, message: string | null, includeUntracked: boolean) : Promise<Result<StashEntry, CommandError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("create_stash", { repoPath, message, includeUntracked }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async applyStash(repoPath: string, index: number) : Promise<Result<GitStatus, CommandError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("apply_stash", { repoPath, index }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async popStash(repoPath: string, index: number) : Promise<Result<GitStatus, CommandError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("pop_stash", { repoPath, index }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async dropStash(repoPath: string, index: number) : Promise<Result<StashEntry[], CommandError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("drop_stash", { repoPath, index }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getSubmoduleDiff(repoPath: string, submodulePath: string, target: DiffTarget) : Promise<Result<SubmoduleDiff, CommandError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_submodule_diff", { repoPath, submodulePath, target }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async listWorktrees(repoPath: string) : Promise<Result<WorktreeInfo[], CommandError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("list_worktrees", { repoPath }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Points the window at another worktree of the repository currently open.
+ */
+async switchWorktree(worktreePath: string) : Promise<Result<AppMode, CommandError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("switch_worktree", { worktreePath }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
 async readFile(filePath: string) : Promise<Result<ReadFileResult, CommandError>> {
     try {
     return { status: "ok", data: await TAURI_INVOKE("read_file", { filePath }) };
@@ -85,23 +280,177 @@ async getCommentsForFile(repoPath: string, filePath: string, fileContents: strin
 /** user-defined types **/
 
 export type AppMode = { type: "empty" } | { type: "git"; path: string } | { type: "file"; fileA: string; fileB: string }
-export type CommandError = { type: "path"; path: string; message: string } | { type: "utf8"; path: string } | { type: "git"; path: string; message: string }
+export type ChangeSummary = { staged: number; unstaged: number; untracked: number }
+export type ChangesetDiff = { files: ChangesetFile[]; truncated: boolean }
+export type ChangesetFile = { diff: FileDiff; 
+/**
+ * Hunks were dropped to stay within the line budget; fetch them with `get_file_diff`.
+ */
+truncated: boolean }
+export type CommandError = { type: "path"; path: string; message: string } | { type: "utf8"; path: string } | { type: "git"; path: string; message: string } | { type: "stale"; path: string; message: string } | { type: "hook"; hook: string; exit_code: number | null; output: string }
 export type Comment = { id: string; filePath: string; anchor: CommentAnchor; body: string; resolved: boolean; createdAt: number; updatedAt: number }
 export type CommentAnchor = { type: "pinned"; line: number } | { type: "tracked"; line: number; context: string } | { type: "orphaned"; last_known_line: number; context: string }
 export type CommentCollection = { comments: Comment[] }
+export type CommitInfo = { id: string; shortId: string; summary: string; body: string | null; author: CommitSignature; committer: CommitSignature; parents: string[] }
+export type CommitSignature = { name: string; email: string; time: number; offsetMinutes: number }
+export type ConflictContents = { base: DiffFile; ours: DiffFile; theirs: DiffFile; working: DiffFile; regions: ConflictRegion[] }
+/**
+ * A run of lines in a conflicted working-tree file; line numbers are 1-based and inclusive,
+ * and a `Conflict` spans its marker lines.
+ */
+export type ConflictRegion = { kind: "common"; startLine: number; endLine: number; lines: string[] } | { kind: "conflict"; startLine: number; endLine: number; oursLabel: string; theirsLabel: string; ours: string[]; base: string[] | null; theirs: string[] }
+export type ConflictResolution = { choice: "ours" } | { choice: "theirs" } | 
+/**
+ * Ours followed by theirs.
+ */
+{ choice: "both" } | { choice: "custom"; lines: string[] }
+/**
+ * Line diff algorithm. There is no histogram variant because libgit2 exposes no flag for it;
+ * `Patience` is the closest available choice.
+ */
+export type DiffAlgorithm = "myers" | "minimal" | "patience"
 export type DiffContent = { contentType: "text"; hunks: DiffHunk[] } | { contentType: "binary" }
 export type DiffFile = { name: string; lang: string | null; content: FileContent | null }
 export type DiffHunk = { oldStart: number; oldLines: number; newStart: number; newLines: number; header: string; lines: DiffLine[] }
-export type DiffLine = { changeType: LineChangeType; content: string; oldLineNo: number | null; newLineNo: number | null }
-export type DiffTarget = "staged" | "unstaged"
+export type DiffLine = { changeType: LineChangeType; content: string; oldLineNo: number | null; newLineNo: number | null; inlineChanges: InlineChange[]; 
+/**
+ * Attribution of the old-side line; only set on deletions when `blame_deletions` is enabled.
+ */
+blame: LineBlame | null }
+export type DiffOptions = { contextLines: number; interhunkLines: number; whitespace: WhitespaceMode; algorithm: DiffAlgorithm; indentHeuristic: boolean; renames: RenameDetection; 
+/**
+ * Minimum similarity percentage for a rename or copy to be reported.
+ */
+similarityThreshold: number; blameDeletions: boolean }
+export type DiffSide = "old" | "new"
+export type DiffStats = { insertions: number; deletions: number; isBinary: boolean }
+export type DiffTarget = "staged" | "unstaged" | 
+/**
+ * Compares HEAD directly against the working tree, including untracked files.
+ */
+"uncommitted" | 
+/**
+ * Compares two revisions. Without `from`, `to` is compared against its first parent.
+ */
+{ range: { from: string | null; to: string } } | 
+/**
+ * Compares `head` against its merge-base with `base`, like `git diff base...head`.
+ */
+{ mergeBase: { base: string; head: string } } | 
+/**
+ * Compares `stash@{index}` against the commit it was created on, including untracked files.
+ */
+{ stash: { index: number } }
+/**
+ * A working-tree snapshot taken before a discard, restorable with `restore_discard`.
+ */
+export type DiscardRecord = { id: string; filePath: string; 
+/**
+ * Blob holding the discarded contents; `None` if the file did not exist.
+ */
+blobId: string | null; 
+/**
+ * Whether only some hunks or lines were discarded.
+ */
+partial: boolean; createdAt: number }
 export type FileContent = { type: "text"; contents: string } | { type: "binary"; size: number }
-export type FileDiff = { path: string; oldPath: string | null; content: DiffContent }
-export type FileEntry = { path: string; kind: FileEntryKind }
-export type FileEntryKind = { status: "added" } | { status: "modified" } | { status: "deleted" } | { status: "renamed"; old_path: string } | { status: "untracked" } | { status: "typechange" } | { status: "conflicted" }
+export type FileDiff = { path: string; oldPath: string | null; similarity: number | null; content: DiffContent }
+export type FileEntry = { path: string; kind: FileEntryKind; 
+/**
+ * `None` where line counts aren't computed, such as file history entries.
+ */
+stats: DiffStats | null }
+export type FileEntryKind = { status: "added" } | { status: "modified" } | { status: "deleted" } | { status: "renamed"; old_path: string; similarity: number } | { status: "copied"; old_path: string; similarity: number } | { status: "untracked" } | { status: "typechange" } | { status: "conflicted" } | 
+/**
+ * A gitlink change; the dirty flags describe the submodule's own working tree.
+ */
+{ status: "submodule"; oldCommit: string | null; newCommit: string | null; modifiedContent: boolean; untrackedContent: boolean }
+/**
+ * One commit in a file's history; `target` selects that commit's diff for `get_file_diff`.
+ */
+export type FileHistoryEntry = { commit: CommitInfo; file: FileEntry; target: DiffTarget }
+export type FileHistoryPage = { entries: FileHistoryEntry[]; hasMore: boolean }
 export type GitFileContents = { oldFile: DiffFile; newFile: DiffFile }
-export type GitStatus = { staged: FileEntry[]; unstaged: FileEntry[]; untracked: FileEntry[] }
+export type GitStatus = { 
+/**
+ * Bare repositories have no index or working tree, so the file lists are always empty.
+ */
+bare: boolean; head: HeadState; upstream: UpstreamStatus | null; operation: RepoOperation | null; staged: FileEntry[]; unstaged: FileEntry[]; untracked: FileEntry[]; 
+/**
+ * Staged, unstaged and untracked changes merged per path, relative to HEAD.
+ */
+uncommitted: FileEntry[] }
+export type HeadState = { type: "branch"; name: string } | { type: "detached"; commitId: string } | 
+/**
+ * A branch with no commits yet.
+ */
+{ type: "unborn"; name: string }
+export type HunkSelection = { hunk: DiffHunk; 
+/**
+ * Indices into `hunk.lines`; `None` selects the whole hunk.
+ */
+lines: number[] | null }
+/**
+ * A changed span within a line, in UTF-16 code unit offsets (`start` inclusive, `end`
+ * exclusive) so JavaScript can slice the line directly.
+ */
+export type InlineChange = { start: number; end: number }
+export type LineBlame = { state: "committed"; commitId: string; summary: string; author: CommitSignature } | { state: "uncommitted" }
 export type LineChangeType = "context" | "addition" | "deletion"
+export type LogOptions = { 
+/**
+ * Revision to start walking from; defaults to HEAD.
+ */
+start: string | null; path: string | null; 
+/**
+ * Case-insensitive substring matched against author name and email.
+ */
+author: string | null; firstParent: boolean; offset: number; limit: number }
+export type LogPage = { commits: CommitInfo[]; hasMore: boolean }
+export type PatchAction = 
+/**
+ * Applies changes from the `Unstaged` diff to the index.
+ */
+"stage" | 
+/**
+ * Reverts changes in the `Staged` diff from the index.
+ */
+"unstage" | 
+/**
+ * Reverts changes in the `Unstaged` diff from the working tree.
+ */
+"discard"
 export type ReadFileResult = { name: string; contents: string; lang: string | null; isBinary: boolean }
+export type RenameDetection = "off" | "renames" | "renamesAndCopies"
+export type RepoOperation = { type: "merge" } | 
+/**
+ * `step` and `total` are unknown when git hasn't written its progress files yet.
+ */
+{ type: "rebase"; step: number | null; total: number | null } | { type: "cherryPick" } | { type: "revert" } | { type: "bisect" } | { type: "applyMailbox" }
+export type StashEntry = { index: number; id: string; message: string; author: CommitSignature }
+/**
+ * Commits between a submodule's old and new pointers, like `git diff --submodule=log`.
+ */
+export type SubmoduleDiff = { path: string; 
+/**
+ * Absolute path of the checked-out submodule, usable as a `repo_path`; `None` if not initialized.
+ */
+repoPath: string | null; oldCommit: string | null; newCommit: string | null; added: CommitInfo[]; removed: CommitInfo[]; 
+/**
+ * `added` or `removed` stops at the newest 100 commits.
+ */
+truncated: boolean }
+export type UpstreamStatus = { name: string; ahead: number; behind: number }
+export type WhitespaceMode = "show" | "ignoreEol" | "ignoreChange" | "ignoreAll"
+export type WorktreeInfo = { 
+/**
+ * `None` for the main worktree.
+ */
+name: string | null; path: string; isCurrent: boolean; locked: boolean; 
+/**
+ * `None` when the worktree directory is missing or cannot be opened.
+ */
+head: HeadState | null; changes: ChangeSummary | null }
 
 /** tauri-specta globals **/
 